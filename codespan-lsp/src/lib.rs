@@ -163,9 +163,11 @@ test
         .unwrap();
         assert_eq!(
             Location {
+                byte_index: pos,
                 // One-based
                 line_number: 3 + 1,
                 column_number: 2 + 1,
+                line_start_byte: 29,
             },
             files.location(file_id, pos).unwrap()
         );
@@ -0,0 +1,122 @@
+//! A benchmark comparing `term::emit` with `term::emit_resolved`, for a file
+//! database without a line index, where every line lookup scans the source.
+//!
+//! To run this benchmark, execute the following command from the top level of
+//! this repository:
+//!
+//! ```sh
+//! cargo bench --bench emit_resolved
+//! ```
+
+use codespan_reporting::diagnostic::{Diagnostic, Label, LabelStyle};
+use codespan_reporting::files::{Error, Files};
+use codespan_reporting::term::{self, termcolor::NoColor, Config, ResolvedLocation};
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+const NUM_LINES: usize = 20_000;
+const NUM_LABELS: usize = 8;
+const ITERATIONS: usize = 200;
+
+/// A single file that looks up lines by scanning its source.
+struct ScanningFile {
+    source: String,
+}
+
+impl ScanningFile {
+    fn line_starts(&self) -> impl '_ + Iterator<Item = usize> {
+        std::iter::once(0).chain(self.source.match_indices('\n').map(|(i, _)| i + 1))
+    }
+}
+
+impl<'a> Files<'a> for ScanningFile {
+    type FileId = ();
+    type Name = &'static str;
+    type Source = &'a str;
+
+    fn name(&'a self, (): ()) -> Result<&'static str, Error> {
+        Ok("bench.txt")
+    }
+
+    fn source(&'a self, (): ()) -> Result<&'a str, Error> {
+        Ok(&self.source)
+    }
+
+    fn line_index(&'a self, (): (), byte_index: usize) -> Result<usize, Error> {
+        Ok(self
+            .line_starts()
+            .take_while(|&line_start| line_start <= byte_index)
+            .count()
+            - 1)
+    }
+
+    fn line_range(&'a self, (): (), line_index: usize) -> Result<Range<usize>, Error> {
+        let mut line_starts = self.line_starts().skip(line_index);
+        let start = line_starts.next().ok_or(Error::LineTooLarge {
+            given: line_index,
+            max: NUM_LINES,
+        })?;
+        let end = line_starts.next().unwrap_or(self.source.len());
+        Ok(start..end)
+    }
+}
+
+fn time(mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS as u32
+}
+
+fn main() {
+    let file = ScanningFile {
+        source: (0..NUM_LINES)
+            .map(|i| format!("let x{} = {};\n", i, i))
+            .collect(),
+    };
+
+    // Labels spread evenly across the file, with their locations resolved up
+    // front, as a tool with its own position index would.
+    let mut labels = Vec::new();
+    let mut resolved_labels = Vec::new();
+    for i in 0..NUM_LABELS {
+        let style = match i {
+            0 => LabelStyle::Primary,
+            _ => LabelStyle::Secondary,
+        };
+        let line_index = i * NUM_LINES / NUM_LABELS;
+        let line_range = file.line_range((), line_index).unwrap();
+        let location = ResolvedLocation {
+            name: "bench.txt",
+            line_number: line_index + 1,
+            column_number: 5,
+            line_text: &file.source[line_range.clone()],
+        };
+        labels.push(Label::new(
+            style,
+            (),
+            (line_range.start + 4)..(line_range.start + 6),
+        ));
+        resolved_labels.push(Label::new(style, location, 4..6));
+    }
+    let diagnostic = Diagnostic::error()
+        .with_message("unused variable")
+        .with_labels(labels);
+    let resolved = Diagnostic::error()
+        .with_message("unused variable")
+        .with_labels(resolved_labels);
+
+    let config = Config::default();
+    let mut writer = NoColor::new(std::io::sink());
+
+    let emit = time(|| {
+        term::emit(&mut writer, &config, &file, &diagnostic).unwrap();
+    });
+    let emit_resolved = time(|| {
+        term::emit_resolved(&mut writer, &config, &resolved).unwrap();
+    });
+
+    println!("emit:          {:>10.2?} per diagnostic", emit);
+    println!("emit_resolved: {:>10.2?} per diagnostic", emit_resolved);
+}
@@ -2,9 +2,12 @@
 
 #[cfg(feature = "serialization")]
 use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
 use std::ops::Range;
 use std::string::ToString;
 
+use crate::files::{self, Files};
+
 /// A severity level for diagnostic messages.
 ///
 /// These are ordered in the following way:
@@ -54,6 +57,57 @@ pub struct Label<FileId> {
     /// An optional message to provide some additional information for the
     /// underlined code. These should not include line breaks.
     pub message: String,
+    /// An optional block of text that is rendered underneath the label. Unlike
+    /// the message, this may include line breaks.
+    #[cfg_attr(feature = "serialization", serde(default))]
+    pub detail: Option<String>,
+    /// An optional one-based display column to point at, for labels that refer
+    /// to a visual position rather than to a piece of code, such as where some
+    /// code should be aligned to. If this is set, the label is rendered as a
+    /// single caret at this column of the line containing the start of the
+    /// range, and the rest of the range is ignored. The column may be past the
+    /// end of the line.
+    #[cfg_attr(feature = "serialization", serde(default))]
+    pub display_column: Option<usize>,
+    /// An optional certainty of the label, as a percentage from `0` to `100`,
+    /// for labels that might not be accurate, such as the findings of a
+    /// heuristic.
+    /// Labels with a certainty below [`Config::certainty_threshold`] are
+    /// rendered as uncertain.
+    ///
+    /// [`Config::certainty_threshold`]: crate::term::Config::certainty_threshold
+    #[cfg_attr(feature = "serialization", serde(default))]
+    pub certainty: Option<u8>,
+    /// An optional tag for associating the label with data of the caller, such
+    /// as the rule that produced it. The tag is not rendered, but is passed
+    /// through to [`LabelLocation::tag`].
+    ///
+    /// [`LabelLocation::tag`]: crate::term::LabelLocation::tag
+    #[cfg_attr(feature = "serialization", serde(default))]
+    pub tag: Option<u64>,
+    /// An optional severity whose colors the label is rendered with, instead
+    /// of the colors of its style, such as for marking code that is suspicious
+    /// but not the cause of an error. This does not change how the label is
+    /// laid out, and is only used for labels that fit on a single line.
+    #[cfg_attr(feature = "serialization", serde(default))]
+    pub severity_hint: Option<Severity>,
+    /// An optional line of source text that the label points at instead of a
+    /// line of the file, such as code produced by a macro expansion. If this
+    /// is set, the range is relative to the start of this text, and the line
+    /// is rendered with a [`Chars::synthetic_line_marker`] in place of its
+    /// line number. Synthetic lines are only rendered by
+    /// [`DisplayStyle::Rich`], and are skipped by the other display styles.
+    ///
+    /// [`Chars::synthetic_line_marker`]: crate::term::Chars::synthetic_line_marker
+    /// [`DisplayStyle::Rich`]: crate::term::DisplayStyle::Rich
+    #[cfg_attr(feature = "serialization", serde(default))]
+    pub synthetic_line: Option<String>,
+    /// Whether to only render the message of the label, without underlining
+    /// its range, for labels that are about a line rather than a precise piece
+    /// of code. The message is connected to the start of the range instead.
+    /// This is only used for labels that fit on a single line.
+    #[cfg_attr(feature = "serialization", serde(default))]
+    pub message_only: bool,
 }
 
 impl<FileId> Label<FileId> {
@@ -68,6 +122,13 @@ impl<FileId> Label<FileId> {
             file_id,
             range: range.into(),
             message: String::new(),
+            detail: None,
+            display_column: None,
+            certainty: None,
+            tag: None,
+            severity_hint: None,
+            synthetic_line: None,
+            message_only: false,
         }
     }
 
@@ -85,12 +146,91 @@ impl<FileId> Label<FileId> {
         Label::new(LabelStyle::Secondary, file_id, range)
     }
 
+    /// Create a new label covering `len` bytes, starting at the byte index `start`.
+    ///
+    /// The end of the range saturates at [`usize::MAX`] instead of overflowing.
+    pub fn at(style: LabelStyle, file_id: FileId, start: usize, len: usize) -> Label<FileId> {
+        Label::new(style, file_id, start..start.saturating_add(len))
+    }
+
+    /// Create a new label with a style of [`LabelStyle::Primary`], covering
+    /// `len` bytes starting at the byte index `start`.
+    ///
+    /// [`LabelStyle::Primary`]: LabelStyle::Primary
+    pub fn primary_at(file_id: FileId, start: usize, len: usize) -> Label<FileId> {
+        Label::at(LabelStyle::Primary, file_id, start, len)
+    }
+
+    /// Create a new label with a style of [`LabelStyle::Secondary`], covering
+    /// `len` bytes starting at the byte index `start`.
+    ///
+    /// [`LabelStyle::Secondary`]: LabelStyle::Secondary
+    pub fn secondary_at(file_id: FileId, start: usize, len: usize) -> Label<FileId> {
+        Label::at(LabelStyle::Secondary, file_id, start, len)
+    }
+
     /// Set the message for the diagnostic. The old message (if any) is discarded.
     pub fn with_message(mut self, message: impl ToString) -> Label<FileId> {
         self.message = message.to_string();
         self
     }
 
+    /// Set the detail for the label. The old detail (if any) is discarded.
+    pub fn with_detail(mut self, detail: impl ToString) -> Label<FileId> {
+        self.detail = Some(detail.to_string());
+        self
+    }
+
+    /// Set the display column for the label to point at. The old display
+    /// column (if any) is discarded.
+    ///
+    /// ```rust
+    /// use codespan_reporting::diagnostic::Label;
+    /// use codespan_reporting::files::{Files, SimpleFile};
+    ///
+    /// let file = SimpleFile::new("test", "if x {\n  a\n   b\n}\n");
+    /// // Point at the third column of the third line.
+    /// let line_start = file.line_range((), 2).unwrap().start;
+    /// let label = Label::primary((), line_start..line_start).with_display_column(3);
+    /// ```
+    pub fn with_display_column(mut self, display_column: usize) -> Label<FileId> {
+        self.display_column = Some(display_column);
+        self
+    }
+
+    /// Set the certainty of the label, as a percentage. The old certainty (if
+    /// any) is discarded.
+    pub fn with_certainty(mut self, certainty: u8) -> Label<FileId> {
+        self.certainty = Some(certainty);
+        self
+    }
+
+    /// Set the tag of the label. The old tag (if any) is discarded.
+    pub fn with_tag(mut self, tag: u64) -> Label<FileId> {
+        self.tag = Some(tag);
+        self
+    }
+
+    /// Set the severity whose colors the label is rendered with.
+    pub fn with_severity_hint(mut self, severity: Severity) -> Label<FileId> {
+        self.severity_hint = Some(severity);
+        self
+    }
+
+    /// Set the synthesized line of source text that the label points at. The
+    /// old line (if any) is discarded.
+    pub fn with_synthetic_line(mut self, line: impl Into<String>) -> Label<FileId> {
+        self.synthetic_line = Some(line.into());
+        self
+    }
+
+    /// Set whether to only render the message of the label, without an
+    /// underline.
+    pub fn with_message_only(mut self, message_only: bool) -> Label<FileId> {
+        self.message_only = message_only;
+        self
+    }
+
     /// Set the file id. The old file id (if any) is discarded.
     pub fn with_file<NewFileId>(self, file_id: NewFileId) -> Label<NewFileId> {
         Label {
@@ -98,8 +238,44 @@ impl<FileId> Label<FileId> {
             file_id,
             range: self.range,
             message: self.message,
+            detail: self.detail,
+            display_column: self.display_column,
+            certainty: self.certainty,
+            tag: self.tag,
+            severity_hint: self.severity_hint,
+            synthetic_line: self.synthetic_line,
+            message_only: self.message_only,
+        }
+    }
+
+    /// Convert the file id with a function.
+    pub fn map_file<NewFileId>(self, f: impl FnOnce(FileId) -> NewFileId) -> Label<NewFileId> {
+        match self.try_map_file(|file_id| Ok::<_, Infallible>(f(file_id))) {
+            Ok(label) => label,
+            Err(never) => match never {},
         }
     }
+
+    /// Convert the file id with a function that can fail, returning the error
+    /// of the function if it fails.
+    pub fn try_map_file<NewFileId, E>(
+        self,
+        f: impl FnOnce(FileId) -> Result<NewFileId, E>,
+    ) -> Result<Label<NewFileId>, E> {
+        Ok(Label {
+            style: self.style,
+            file_id: f(self.file_id)?,
+            range: self.range,
+            message: self.message,
+            detail: self.detail,
+            display_column: self.display_column,
+            certainty: self.certainty,
+            tag: self.tag,
+            severity_hint: self.severity_hint,
+            synthetic_line: self.synthetic_line,
+            message_only: self.message_only,
+        })
+    }
 }
 
 // use a separate impl so we do not have to specify the type like this in e.g.
@@ -117,6 +293,13 @@ impl Label<()> {
             file_id: (),
             range: range.into(),
             message: String::new(),
+            detail: None,
+            display_column: None,
+            certainty: None,
+            tag: None,
+            severity_hint: None,
+            synthetic_line: None,
+            message_only: false,
         }
     }
 
@@ -139,10 +322,106 @@ impl Label<()> {
     }
 }
 
+/// How confident a tool can be that applying a [`Suggestion`] results in the
+/// intended code.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub enum Applicability {
+    /// The suggestion is definitely what the user intended, and can be
+    /// applied automatically.
+    MachineApplicable,
+    /// The suggestion may be what the user intended, but it is uncertain, so
+    /// it should be reviewed before it is applied.
+    MaybeIncorrect,
+    /// The suggestion contains placeholders like `(...)` or `{ /* fields */ }`
+    /// that need to be filled in before it is applied.
+    HasPlaceholders,
+}
+
+/// A suggested change to the source code, made up of text replacements that
+/// should be applied together.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct Suggestion<FileId> {
+    /// A message describing the suggestion, such as ``consider adding `;` ``.
+    pub message: String,
+    /// The replacements that make up the suggestion, each replacing a byte
+    /// range in a file with some text. Insertions are replacements of an empty
+    /// range, and deletions are replacements with an empty string.
+    pub replacements: Vec<(FileId, Range<usize>, String)>,
+    /// How confident a tool can be in applying the suggestion.
+    pub applicability: Applicability,
+}
+
+impl<FileId> Suggestion<FileId> {
+    /// Create a new suggestion without any replacements.
+    pub fn new(message: impl ToString, applicability: Applicability) -> Suggestion<FileId> {
+        Suggestion {
+            message: message.to_string(),
+            replacements: Vec::new(),
+            applicability,
+        }
+    }
+
+    /// Add a replacement of the given range of a file to the suggestion.
+    pub fn with_replacement(
+        mut self,
+        file_id: FileId,
+        range: impl Into<Range<usize>>,
+        replacement: impl ToString,
+    ) -> Suggestion<FileId> {
+        self.replacements
+            .push((file_id, range.into(), replacement.to_string()));
+        self
+    }
+
+    /// Set the file id for all replacements of this suggestion.
+    pub fn with_file<NewFileId: Clone>(self, file_id: NewFileId) -> Suggestion<NewFileId> {
+        Suggestion {
+            message: self.message,
+            replacements: self
+                .replacements
+                .into_iter()
+                .map(|(_, range, replacement)| (file_id.clone(), range, replacement))
+                .collect(),
+            applicability: self.applicability,
+        }
+    }
+
+    /// Convert the file ids of the replacements of this suggestion with a
+    /// function.
+    pub fn map_file<NewFileId>(
+        self,
+        mut f: impl FnMut(FileId) -> NewFileId,
+    ) -> Suggestion<NewFileId> {
+        match self.try_map_file(|file_id| Ok::<_, Infallible>(f(file_id))) {
+            Ok(suggestion) => suggestion,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Convert the file ids of the replacements of this suggestion with a
+    /// function that can fail, returning the first error of the function.
+    pub fn try_map_file<NewFileId, E>(
+        self,
+        mut f: impl FnMut(FileId) -> Result<NewFileId, E>,
+    ) -> Result<Suggestion<NewFileId>, E> {
+        Ok(Suggestion {
+            message: self.message,
+            replacements: self
+                .replacements
+                .into_iter()
+                .map(|(file_id, range, replacement)| Ok((f(file_id)?, range, replacement)))
+                .collect::<Result<_, E>>()?,
+            applicability: self.applicability,
+        })
+    }
+}
+
 /// Represents a diagnostic message that can provide information like errors and
 /// warnings to the user.
 ///
-/// The position of a Diagnostic is considered to be the position of the [`Label`] that has the earliest starting position and has the highest style which appears in all the labels of the diagnostic.
+/// The position of a Diagnostic is considered to be the position of the [`Label`] that has the earliest starting position and has the highest style which appears in all the labels of the diagnostic, which is returned by [`Diagnostic::primary_label`].
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
 pub struct Diagnostic<FileId> {
@@ -163,6 +442,15 @@ pub struct Diagnostic<FileId> {
     /// Notes that are associated with the primary cause of the diagnostic.
     /// These can include line breaks for improved formatting.
     pub notes: Vec<String>,
+    /// An ordered walkthrough of labelled steps, each with a description, for
+    /// tracing a value through the source code. Unlike labels, the steps are
+    /// rendered in the order that they are given in, with their step numbers.
+    #[cfg_attr(feature = "serialization", serde(default))]
+    pub steps: Vec<(Label<FileId>, String)>,
+    /// Suggested changes to the source code that fix the diagnostic, for
+    /// tools that apply fixes automatically. These are not rendered.
+    #[cfg_attr(feature = "serialization", serde(default))]
+    pub suggestions: Vec<Suggestion<FileId>>,
 }
 
 impl<FileId> Diagnostic<FileId> {
@@ -174,6 +462,8 @@ impl<FileId> Diagnostic<FileId> {
             message: String::new(),
             labels: Vec::new(),
             notes: Vec::new(),
+            steps: Vec::new(),
+            suggestions: Vec::new(),
         }
     }
 
@@ -239,6 +529,28 @@ impl<FileId> Diagnostic<FileId> {
         self
     }
 
+    /// Add a label to the diagnostic if the condition is true.
+    pub fn with_label_if(mut self, cond: bool, label: Label<FileId>) -> Diagnostic<FileId> {
+        if cond {
+            self.labels.push(label);
+        }
+        self
+    }
+
+    /// Add a label to the diagnostic if there is one.
+    pub fn with_optional_label(mut self, label: Option<Label<FileId>>) -> Diagnostic<FileId> {
+        self.labels.extend(label);
+        self
+    }
+
+    /// Add a note to the diagnostic.
+    ///
+    /// The note may contain line breaks, which are preserved when rendering.
+    pub fn with_note(mut self, note: impl ToString) -> Diagnostic<FileId> {
+        self.notes.push(note.to_string());
+        self
+    }
+
     /// Add some notes to the diagnostic.
     pub fn with_notes(mut self, mut notes: Vec<String>) -> Diagnostic<FileId> {
         self.notes.append(&mut notes);
@@ -254,8 +566,77 @@ impl<FileId> Diagnostic<FileId> {
         self
     }
 
-    /// Set the file id for all labels in this Diagnostic by calling
-    /// [`Label::with_file`] on each label.
+    /// Add some steps to the diagnostic, each made up of a label and a
+    /// description of the step.
+    pub fn with_steps(mut self, mut steps: Vec<(Label<FileId>, String)>) -> Diagnostic<FileId> {
+        self.steps.append(&mut steps);
+        self
+    }
+
+    /// Add a suggestion to the diagnostic.
+    pub fn with_suggestion(mut self, suggestion: Suggestion<FileId>) -> Diagnostic<FileId> {
+        self.suggestions.push(suggestion);
+        self
+    }
+
+    /// Add some suggestions to the diagnostic.
+    pub fn with_suggestions_iter(
+        mut self,
+        suggestions: impl IntoIterator<Item = Suggestion<FileId>>,
+    ) -> Diagnostic<FileId> {
+        self.suggestions.extend(suggestions);
+        self
+    }
+
+    /// The label that determines the position of the diagnostic.
+    ///
+    /// This is the primary label that starts first, or the secondary label
+    /// that starts first if the diagnostic has no primary labels. Labels that
+    /// start at the same position are ordered by their end, and then by their
+    /// order in [`Diagnostic::labels`]. Returns `None` if the diagnostic has no
+    /// labels.
+    pub fn primary_label(&self) -> Option<&Label<FileId>> {
+        self.labels
+            .iter()
+            .min_by_key(|label| primary_label_key(label))
+    }
+
+    /// Whether the diagnostic has labels, but none of them are primary.
+    ///
+    /// This can be used to reject such diagnostics before emitting them.
+    /// Otherwise they are rendered according to [`Config::require_primary`].
+    ///
+    /// [`Config::require_primary`]: crate::term::Config::require_primary
+    pub fn is_missing_primary_label(&self) -> bool {
+        !self.labels.is_empty()
+            && (self.labels.iter()).all(|label| label.style == LabelStyle::Secondary)
+    }
+
+    /// A mutable reference to the label returned by
+    /// [`Diagnostic::primary_label`].
+    pub fn primary_label_mut(&mut self) -> Option<&mut Label<FileId>> {
+        self.labels
+            .iter_mut()
+            .min_by_key(|label| primary_label_key(label))
+    }
+
+    /// The labels of the diagnostic, in the order that they appear in the
+    /// source code.
+    ///
+    /// Labels are ordered by their start, then by their end, and then with
+    /// primary labels before secondary labels.
+    pub fn sorted_labels(&self) -> Vec<&Label<FileId>> {
+        let mut labels = self.labels.iter().collect::<Vec<_>>();
+        labels.sort_by_key(|label| {
+            let (style, start, end) = primary_label_key(label);
+            (start, end, style)
+        });
+        labels
+    }
+
+    /// Set the file id for all labels and suggestions in this Diagnostic by
+    /// calling [`Label::with_file`] on each label and [`Suggestion::with_file`]
+    /// on each suggestion.
     pub fn with_file<NewFileId: Clone>(mut self, file_id: NewFileId) -> Diagnostic<NewFileId> {
         Diagnostic {
             severity: self.severity,
@@ -267,6 +648,501 @@ impl<FileId> Diagnostic<FileId> {
                 .map(|label| label.with_file(file_id.clone()))
                 .collect(),
             notes: self.notes,
+            steps: self
+                .steps
+                .drain(..)
+                .map(|(label, description)| (label.with_file(file_id.clone()), description))
+                .collect(),
+            suggestions: self
+                .suggestions
+                .drain(..)
+                .map(|suggestion| suggestion.with_file(file_id.clone()))
+                .collect(),
+        }
+    }
+
+    /// Convert the file ids of all labels, steps, and suggestions in this
+    /// Diagnostic with a function, which is called on each file id separately.
+    pub fn map_file<NewFileId>(
+        self,
+        mut f: impl FnMut(FileId) -> NewFileId,
+    ) -> Diagnostic<NewFileId> {
+        match self.try_map_file(|file_id| Ok::<_, Infallible>(f(file_id))) {
+            Ok(diagnostic) => diagnostic,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Convert the file ids of all labels, steps, and suggestions in this
+    /// Diagnostic with a function that can fail, which is called on each file
+    /// id separately. The conversion stops at the first error of the function,
+    /// which is returned.
+    pub fn try_map_file<NewFileId, E>(
+        self,
+        mut f: impl FnMut(FileId) -> Result<NewFileId, E>,
+    ) -> Result<Diagnostic<NewFileId>, E> {
+        Ok(Diagnostic {
+            severity: self.severity,
+            code: self.code,
+            message: self.message,
+            labels: self
+                .labels
+                .into_iter()
+                .map(|label| label.try_map_file(&mut f))
+                .collect::<Result<_, E>>()?,
+            notes: self.notes,
+            steps: self
+                .steps
+                .into_iter()
+                .map(|(label, description)| Ok((label.try_map_file(&mut f)?, description)))
+                .collect::<Result<_, E>>()?,
+            suggestions: self
+                .suggestions
+                .into_iter()
+                .map(|suggestion| suggestion.try_map_file(&mut f))
+                .collect::<Result<_, E>>()?,
+        })
+    }
+
+    /// Clamp the ranges of all labels and steps to the bounds of their files,
+    /// such as before emitting a diagnostic from an untrusted source. The start
+    /// of each range is moved back to the nearest char boundary, and its end
+    /// is moved forward to the nearest char boundary. The ranges of labels on
+    /// synthetic lines are clamped to the bounds of the line instead.
+    ///
+    /// Returns an error if a file of a label is missing.
+    pub fn clamp_ranges<'files>(
+        &mut self,
+        files: &'files impl Files<'files, FileId = FileId>,
+    ) -> Result<(), files::Error>
+    where
+        FileId: 'files + Copy,
+    {
+        let step_labels = self.steps.iter_mut().map(|(label, _)| label);
+        for label in self.labels.iter_mut().chain(step_labels) {
+            label.range = match &label.synthetic_line {
+                Some(line) => clamp_range(line, label.range.clone()),
+                None => clamp_range(files.source(label.file_id)?.as_ref(), label.range.clone()),
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Clamp a range to the bounds of the source, widening it to char boundaries.
+fn clamp_range(source: &str, range: Range<usize>) -> Range<usize> {
+    let mut start = usize::min(range.start, source.len());
+    while !source.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = usize::min(usize::max(range.end, start), source.len());
+    while !source.is_char_boundary(end) {
+        end += 1;
+    }
+    start..end
+}
+
+/// A key for ordering labels by their style, with primary labels first, and
+/// then by their range.
+fn primary_label_key<FileId>(label: &Label<FileId>) -> (bool, usize, usize) {
+    (
+        label.style != LabelStyle::Primary,
+        label.range.start,
+        label.range.end,
+    )
+}
+
+impl<FileId: PartialEq> Diagnostic<FileId> {
+    /// The smallest range that encloses all of the labels in the given file,
+    /// such as for selecting the code affected by the diagnostic in an editor.
+    /// Returns `None` if the diagnostic has no labels in the file.
+    pub fn file_span(&self, file_id: &FileId) -> Option<Range<usize>> {
+        self.labels
+            .iter()
+            .filter(|label| label.file_id == *file_id)
+            .map(|label| label.range.clone())
+            .fold(None, |span, range| match span {
+                None => Some(range),
+                Some(span) => {
+                    Some(usize::min(span.start, range.start)..usize::max(span.end, range.end))
+                }
+            })
+    }
+}
+
+impl<FileId: Clone + PartialEq> Diagnostic<FileId> {
+    /// Split the diagnostic into one diagnostic per file, such as for
+    /// publishing diagnostics per document in an editor.
+    ///
+    /// Each of the returned diagnostics has the severity, code, message and
+    /// notes of this diagnostic, along with the labels and steps in its file.
+    /// If `other_labels_as_notes` is `true`, the messages of the labels in
+    /// other files are added as notes, prefixed with the location of the label
+    /// in `files`, such as `lib.rs:1:8: defined here`. Otherwise these labels
+    /// are dropped. The diagnostics are returned in the order that their files
+    /// first appear in the labels, and diagnostics without labels are not
+    /// split.
+    ///
+    /// Each suggestion is kept whole on a single diagnostic, rather than being
+    /// split into its replacements in each file, because applying only some
+    /// of the replacements of a suggestion can leave the code broken. It is
+    /// kept on the diagnostic of the first file that it has a replacement in,
+    /// or on the first diagnostic if none of its replacements are in the files
+    /// of the labels, so that every suggestion is applied at most once.
+    ///
+    /// Returns an error if a file of a label in another file is missing.
+    pub fn split_by_file<'files>(
+        &self,
+        files: &'files impl Files<'files, FileId = FileId>,
+        other_labels_as_notes: bool,
+    ) -> Result<Vec<(FileId, Diagnostic<FileId>)>, files::Error>
+    where
+        FileId: 'files + Copy,
+    {
+        let mut file_ids = Vec::<FileId>::new();
+        for label in &self.labels {
+            if !file_ids.contains(&label.file_id) {
+                file_ids.push(label.file_id);
+            }
+        }
+
+        let mut split = Vec::with_capacity(file_ids.len());
+        for &file_id in &file_ids {
+            let mut diagnostic = Diagnostic {
+                severity: self.severity,
+                code: self.code.clone(),
+                message: self.message.clone(),
+                labels: Vec::new(),
+                notes: self.notes.clone(),
+                steps: Vec::new(),
+                suggestions: Vec::new(),
+            };
+            for label in &self.labels {
+                if label.file_id == file_id {
+                    diagnostic.labels.push(label.clone());
+                } else if other_labels_as_notes && !label.message.is_empty() {
+                    let name = files.name(label.file_id)?;
+                    diagnostic.notes.push(match label.synthetic_line {
+                        // Synthetic lines are not at a position in the file.
+                        Some(_) => format!("{}: {}", name, label.message),
+                        None => {
+                            let location = files.location(label.file_id, label.range.start)?;
+                            format!(
+                                "{}:{}:{}: {}",
+                                name, location.line_number, location.column_number, label.message,
+                            )
+                        }
+                    });
+                }
+            }
+            diagnostic.steps = self
+                .steps
+                .iter()
+                .filter(|(label, _)| label.file_id == file_id)
+                .cloned()
+                .collect();
+            split.push((file_id, diagnostic));
+        }
+
+        for suggestion in &self.suggestions {
+            let index = suggestion
+                .replacements
+                .iter()
+                .find_map(|(id, _, _)| file_ids.iter().position(|file_id| file_id == id))
+                .unwrap_or(0);
+            if let Some((_, diagnostic)) = split.get_mut(index) {
+                diagnostic.suggestions.push(suggestion.clone());
+            }
         }
+
+        Ok(split)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_at() {
+        assert_eq!(Label::primary_at((), 3, 4), Label::primary((), 3..7));
+        assert_eq!(Label::secondary_at((), 3, 0), Label::secondary((), 3..3));
+        assert_eq!(
+            Label::at(LabelStyle::Primary, (), usize::MAX - 1, 1),
+            Label::primary((), (usize::MAX - 1)..usize::MAX),
+        );
+    }
+
+    #[test]
+    fn label_at_saturates() {
+        assert_eq!(
+            Label::primary_at((), usize::MAX - 1, 2),
+            Label::primary((), (usize::MAX - 1)..usize::MAX),
+        );
+        assert_eq!(
+            Label::secondary_at((), usize::MAX, usize::MAX),
+            Label::secondary((), usize::MAX..usize::MAX),
+        );
+    }
+
+    #[test]
+    fn with_note() {
+        let diagnostic = Diagnostic::<()>::error()
+            .with_note("first line\nsecond line")
+            .with_notes(vec!["another note".to_owned()])
+            .with_note(42);
+
+        assert_eq!(
+            diagnostic.notes,
+            vec!["first line\nsecond line", "another note", "42"],
+        );
+    }
+
+    #[test]
+    fn with_suggestion() {
+        let diagnostic = Diagnostic::<()>::error()
+            .with_message("expected `;`")
+            .with_suggestion(
+                Suggestion::new("consider adding `;`", Applicability::MachineApplicable)
+                    .with_replacement((), 9..9, ";"),
+            )
+            .with_suggestions_iter(vec![Suggestion::new(
+                "consider removing the newline",
+                Applicability::MaybeIncorrect,
+            )
+            .with_replacement((), 9..10, "")])
+            .with_file(3);
+
+        assert_eq!(diagnostic.suggestions.len(), 2);
+        assert_eq!(
+            diagnostic.suggestions[0].replacements,
+            vec![(3, 9..9, ";".to_owned())],
+        );
+        assert_eq!(
+            diagnostic.suggestions[1].applicability,
+            Applicability::MaybeIncorrect,
+        );
+    }
+
+    #[test]
+    fn map_file() {
+        let diagnostic =
+            Diagnostic::error()
+                .with_labels(vec![
+                    Label::primary("main.rs", 0..1),
+                    Label::secondary("lib.rs", 2..3),
+                ])
+                .with_steps(vec![(Label::primary("lib.rs", 4..5), "moved".to_owned())])
+                .with_suggestion(
+                    Suggestion::new("remove this", Applicability::MachineApplicable)
+                        .with_replacement("main.rs", 0..1, ""),
+                );
+
+        let file_ids = ["main.rs", "lib.rs"];
+        let lookup = |name| match file_ids.iter().position(|id| *id == name) {
+            Some(file_id) => Ok(file_id),
+            None => Err(name),
+        };
+
+        let mapped = diagnostic.clone().try_map_file(lookup).unwrap();
+        let label_ids = mapped.labels.iter().map(|label| label.file_id);
+        assert_eq!(label_ids.collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(mapped.steps[0].0.file_id, 1);
+        assert_eq!(mapped.suggestions[0].replacements[0].0, 0);
+        assert_eq!(
+            diagnostic.clone().map_file(|name| name.len()).labels[1].file_id,
+            6
+        );
+
+        let unknown = diagnostic.with_labels(vec![Label::primary("missing.rs", 0..0)]);
+        assert_eq!(unknown.try_map_file(lookup), Err("missing.rs"));
+        assert_eq!(
+            Label::primary("lib.rs", 0..1).try_map_file(lookup),
+            Ok(Label::primary(1, 0..1)),
+        );
+    }
+
+    #[test]
+    fn primary_label() {
+        let mut diagnostic = Diagnostic::<()>::error();
+        assert_eq!(diagnostic.primary_label(), None);
+        assert!(!diagnostic.is_missing_primary_label());
+
+        diagnostic = diagnostic.with_labels(vec![
+            Label::secondary((), 8..12),
+            Label::secondary((), 4..9),
+            Label::secondary((), 4..6),
+        ]);
+        assert_eq!(
+            diagnostic.primary_label(),
+            Some(&Label::secondary((), 4..6))
+        );
+        assert!(diagnostic.is_missing_primary_label());
+
+        diagnostic =
+            diagnostic.with_labels(vec![Label::primary((), 20..21), Label::primary((), 10..11)]);
+        assert_eq!(
+            diagnostic.primary_label(),
+            Some(&Label::primary((), 10..11))
+        );
+        assert!(!diagnostic.is_missing_primary_label());
+
+        diagnostic.primary_label_mut().unwrap().message = "here".to_owned();
+        assert_eq!(diagnostic.labels[4].message, "here");
+
+        assert_eq!(
+            diagnostic
+                .sorted_labels()
+                .iter()
+                .map(|label| label.range.clone())
+                .collect::<Vec<_>>(),
+            vec![4..6, 4..9, 8..12, 10..11, 20..21],
+        );
+    }
+
+    #[test]
+    fn file_span() {
+        let diagnostic = Diagnostic::error().with_labels(vec![
+            Label::secondary("main.rs", 20..24),
+            Label::primary("main.rs", 4..9),
+            Label::secondary("lib.rs", 0..40),
+            Label::secondary("main.rs", 12..30),
+        ]);
+
+        assert_eq!(diagnostic.file_span(&"main.rs"), Some(4..30));
+        assert_eq!(diagnostic.file_span(&"lib.rs"), Some(0..40));
+        assert_eq!(diagnostic.file_span(&"missing.rs"), None);
+    }
+
+    #[test]
+    fn split_by_file() {
+        use crate::files::StaticFiles;
+
+        let mut files = StaticFiles::new();
+        files.add("main.rs", "let five: u32 = {\n    lib::five()\n};\n");
+        files.add(
+            "lib.rs",
+            "pub fn five() -> &'static str {\n    \"five\"\n}\n",
+        );
+
+        let fix_both = Suggestion::new("return a `u32`", Applicability::MaybeIncorrect)
+            .with_replacement("lib.rs", 17..29, "u32")
+            .with_replacement("lib.rs", 36..42, "5")
+            .with_replacement("main.rs", 10..13, "&str");
+        let fix_main = Suggestion::new("change the type", Applicability::MaybeIncorrect)
+            .with_replacement("main.rs", 10..13, "&str");
+        let fix_other = Suggestion::new("add a test", Applicability::HasPlaceholders)
+            .with_replacement("tests.rs", 0..0, "#[test] fn five() { /* ... */ }");
+        let diagnostic = Diagnostic::error()
+            .with_message("mismatched types")
+            .with_labels(vec![
+                Label::primary("main.rs", 22..33).with_message("expected `u32`"),
+                Label::secondary("lib.rs", 7..11).with_message("defined here"),
+                Label::secondary("main.rs", 4..8),
+            ])
+            .with_note("expected `u32`, found `&str`")
+            .with_suggestions_iter(vec![fix_both.clone(), fix_main.clone(), fix_other.clone()]);
+
+        let split = diagnostic.split_by_file(&files, false).unwrap();
+        assert_eq!(split.len(), 2);
+        assert_eq!(split[0].0, "main.rs");
+        assert_eq!(
+            split[0].1.labels,
+            vec![diagnostic.labels[0].clone(), diagnostic.labels[2].clone()],
+        );
+        assert_eq!(split[0].1.notes, diagnostic.notes);
+        assert_eq!(split[1].0, "lib.rs");
+        assert_eq!(split[1].1.labels, vec![diagnostic.labels[1].clone()]);
+        assert_eq!(split[1].1.message, "mismatched types");
+        // Suggestions are not duplicated, or split into their replacements.
+        assert_eq!(split[0].1.suggestions, vec![fix_main, fix_other]);
+        assert_eq!(split[1].1.suggestions, vec![fix_both]);
+
+        let split = diagnostic.split_by_file(&files, true).unwrap();
+        assert_eq!(
+            split[0].1.notes,
+            vec!["expected `u32`, found `&str`", "lib.rs:1:8: defined here"],
+        );
+        assert_eq!(
+            split[1].1.notes,
+            vec![
+                "expected `u32`, found `&str`",
+                "main.rs:2:5: expected `u32`"
+            ],
+        );
+    }
+
+    #[test]
+    fn with_optional_label() {
+        let diagnostic = Diagnostic::error()
+            .with_message("oh no")
+            .with_labels(vec![Label::primary((), 0..1)]);
+
+        assert_eq!(diagnostic.clone().with_optional_label(None), diagnostic);
+        assert_eq!(
+            diagnostic
+                .clone()
+                .with_label_if(false, Label::secondary((), 2..3)),
+            diagnostic,
+        );
+
+        let labels = diagnostic
+            .with_optional_label(Some(Label::secondary((), 2..3)))
+            .with_label_if(true, Label::secondary((), 4..5))
+            .labels;
+        assert_eq!(
+            labels
+                .iter()
+                .map(|label| label.range.clone())
+                .collect::<Vec<_>>(),
+            [0..1, 2..3, 4..5],
+        );
+    }
+
+    #[test]
+    fn clamp_ranges() {
+        use crate::files::SimpleFiles;
+
+        let mut files = SimpleFiles::new();
+        let id = files.add("test", "let café = 1;\n");
+        let mut diagnostic = Diagnostic::error()
+            .with_labels(vec![
+                Label::primary(id, 10..100),
+                Label::secondary(id, 8..9),
+                Label::secondary(id, 200..300),
+                Label::secondary(id, 0..3).with_synthetic_line("ü"),
+            ])
+            .with_steps(vec![(
+                Label::primary(id, 4..8),
+                String::from("defined here"),
+            )]);
+        diagnostic.clamp_ranges(&files).unwrap();
+
+        let ranges = diagnostic
+            .labels
+            .iter()
+            .map(|label| label.range.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, vec![10..15, 7..9, 15..15, 0..2]);
+        assert_eq!(diagnostic.steps[0].0.range, 4..9);
+
+        let mut diagnostic = Diagnostic::error().with_labels(vec![Label::primary(id + 1, 0..1)]);
+        assert!(matches!(
+            diagnostic.clamp_ranges(&files),
+            Err(files::Error::FileMissing),
+        ));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn suggestion_round_trip() {
+        let diagnostic = Diagnostic::error().with_suggestion(
+            Suggestion::new("consider adding `;`", Applicability::MachineApplicable)
+                .with_replacement(0usize, 9..9, ";"),
+        );
+
+        let json = serde_json::to_string(&diagnostic).unwrap();
+        let round_trip = serde_json::from_str::<Diagnostic<usize>>(&json).unwrap();
+        assert_eq!(round_trip, diagnostic);
     }
 }
@@ -8,6 +8,11 @@
 //!
 //! - [`SimpleFile`]: For single-file use-cases
 //! - [`SimpleFiles`]: For multi-file use-cases
+//! - [`StaticFiles`]: For a fixed set of built-in files, referred to by name
+//! - [`ClosureFiles`]: For files that are loaded on demand by a closure
+//! - [`SubFile`]: For fragments of a larger file, such as embedded languages
+//! - `NormalizedNameFiles`: For matching file names regardless of their case or
+//!   Unicode normalization form, if the `normalize-names` feature is enabled
 //!
 //! These data structures provide a pretty minimal API, however,
 //! so end-users are encouraged to create their own implementations for their
@@ -20,10 +25,24 @@
 //! [`Files`]: Files
 //! [`SimpleFile`]: SimpleFile
 //! [`SimpleFiles`]: SimpleFiles
+//! [`StaticFiles`]: StaticFiles
+//! [`ClosureFiles`]: ClosureFiles
+//! [`SubFile`]: SubFile
 //!
 //! [`salsa`]: https://crates.io/crates/salsa
 
+use std::cell::RefCell;
+#[cfg(feature = "fs")]
+use std::io;
 use std::ops::Range;
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+#[cfg(feature = "normalize-names")]
+use unicode_normalization::UnicodeNormalization;
+
+use crate::diagnostic::{Diagnostic, Label, Suggestion};
 
 /// An enum representing an error that happened while looking up a file or a piece of content in that file.
 #[derive(Debug)]
@@ -106,7 +125,8 @@ pub trait Files<'a> {
     /// This can be implemented efficiently by performing a binary search over
     /// a list of line starts that was computed by calling the [`line_starts`]
     /// function that is exported from the [`files`] module. It might be useful
-    /// to pre-compute and cache these line starts.
+    /// to pre-compute and cache these line starts. Lines should only be
+    /// terminated by `\n`, as they are by [`line_starts`].
     ///
     /// [`line_starts`]: crate::files::line_starts
     /// [`files`]: crate::files
@@ -152,20 +172,211 @@ pub trait Files<'a> {
     }
 
     /// Convenience method for returning line and column number at the given
-    /// byte index in the file.
+    /// byte index in the file, along with the byte index of the start of the line.
     fn location(&'a self, id: Self::FileId, byte_index: usize) -> Result<Location, Error> {
         let line_index = self.line_index(id, byte_index)?;
 
         Ok(Location {
+            byte_index,
             line_number: self.line_number(id, line_index)?,
             column_number: self.column_number(id, line_index, byte_index)?,
+            line_start_byte: self.line_range(id, line_index)?.start,
         })
     }
 
     /// The byte range of line in the source of the file.
     fn line_range(&'a self, id: Self::FileId, line_index: usize) -> Result<Range<usize>, Error>;
+
+    /// The ids of all of the files in the file provider.
+    ///
+    /// # Note for trait implementors
+    ///
+    /// The default implementation returns an empty iterator, so that file
+    /// providers that can not enumerate their files do not need to implement
+    /// this method.
+    fn file_ids(&'a self) -> Box<dyn 'a + Iterator<Item = Self::FileId>> {
+        Box::new(std::iter::empty())
+    }
+
+    /// Convert a byte range in the source of a file into a range of UTF-16 code
+    /// units, which is what editors like Monaco and VS Code use for positions.
+    ///
+    /// ```rust
+    /// use codespan_reporting::files::{Files, SimpleFile};
+    ///
+    /// let file = SimpleFile::new("test", "let 🦀 = \"crab\";");
+    ///
+    /// assert_eq!(file.utf16_range((), 4..8).unwrap(), 4..6);
+    /// assert_eq!(file.utf16_range((), 11..17).unwrap(), 9..15);
+    /// ```
+    fn utf16_range(&'a self, id: Self::FileId, range: Range<usize>) -> Result<Range<usize>, Error> {
+        let source = self.source(id)?;
+        let source = source.as_ref();
+
+        let utf16_index = |byte_index: usize| match source.get(..byte_index) {
+            Some(prefix) => Ok(prefix.encode_utf16().count()),
+            None if byte_index > source.len() => Err(Error::IndexTooLarge {
+                given: byte_index,
+                max: source.len(),
+            }),
+            None => Err(Error::InvalidCharBoundary { given: byte_index }),
+        };
+
+        Ok(utf16_index(range.start)?..utf16_index(range.end)?)
+    }
+
+    /// All of the lines of a file, along with their line numbers and byte ranges.
+    ///
+    /// This looks up the file and its line ranges once, so that the lines can
+    /// then be iterated over without any further lookups.
+    ///
+    /// ```rust
+    /// use codespan_reporting::files::{Files, SimpleFile};
+    ///
+    /// let file = SimpleFile::new("test", "foo\nbar\n");
+    /// let lines = file.lines(()).unwrap();
+    ///
+    /// for (line_number, range, text) in &lines {
+    ///     println!("{} {:?} {:?}", line_number, range, text);
+    /// }
+    /// ```
+    ///
+    /// # Note for trait implementors
+    ///
+    /// The default implementation calls [`line_range`] for every line of the
+    /// file up front. Databases that cache their line starts should return
+    /// [`FileLines::from_line_starts`] instead, which reads the ranges from
+    /// the cache as the lines are iterated over.
+    ///
+    /// [`line_range`]: Files::line_range
+    /// [`FileLines::from_line_starts`]: FileLines::from_line_starts
+    fn lines(&'a self, id: Self::FileId) -> Result<FileLines<'a, Self::Source>, Error> {
+        let source = self.source(id)?;
+        let last_line_index = self.line_index(id, source.as_ref().len())?;
+        let lines = (0..=last_line_index)
+            .map(|line_index| {
+                Ok((
+                    self.line_number(id, line_index)?,
+                    self.line_range(id, line_index)?,
+                ))
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(FileLines {
+            source,
+            lines: LineTable::Ranges(lines),
+        })
+    }
+}
+
+/// The lines of a source file, returned by [`Files::lines`].
+///
+/// Iterating over a reference to this yields the user-facing line number, the
+/// byte range, and the text of each line, including its line ending.
+///
+/// [`Files::lines`]: Files::lines
+#[derive(Debug, Clone)]
+pub struct FileLines<'a, Source> {
+    source: Source,
+    lines: LineTable<'a>,
+}
+
+/// The line ranges of a file, either looked up one line at a time or borrowed
+/// from a cached list of line starts.
+#[derive(Debug, Clone)]
+enum LineTable<'a> {
+    Ranges(Vec<(usize, Range<usize>)>),
+    Starts(&'a [usize]),
+}
+
+impl<'a, Source: AsRef<str>> FileLines<'a, Source> {
+    /// The lines of a file with one-based line numbers, read from the starting
+    /// byte indices of its lines, as returned by [`line_starts`].
+    ///
+    /// This does no work up front, so it is what implementations of
+    /// [`Files::lines`] that cache their line starts should return.
+    ///
+    /// [`line_starts`]: crate::files::line_starts
+    /// [`Files::lines`]: Files::lines
+    pub fn from_line_starts(source: Source, line_starts: &'a [usize]) -> FileLines<'a, Source> {
+        FileLines {
+            source,
+            lines: LineTable::Starts(line_starts),
+        }
+    }
+
+    /// An iterator over the line number, byte range, and text of each line.
+    pub fn iter(&self) -> FileLinesIter<'_> {
+        FileLinesIter {
+            source: self.source.as_ref(),
+            lines: &self.lines,
+            line_index: 0,
+        }
+    }
+
+    /// The number of lines in the file.
+    pub fn len(&self) -> usize {
+        match &self.lines {
+            LineTable::Ranges(lines) => lines.len(),
+            LineTable::Starts(line_starts) => line_starts.len(),
+        }
+    }
+
+    /// Whether the file has no lines.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<'lines, 'a, Source: AsRef<str>> IntoIterator for &'lines FileLines<'a, Source> {
+    type Item = (usize, Range<usize>, &'lines str);
+    type IntoIter = FileLinesIter<'lines>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the lines of a file, returned by [`FileLines::iter`].
+///
+/// [`FileLines::iter`]: FileLines::iter
+#[derive(Debug, Clone)]
+pub struct FileLinesIter<'lines> {
+    source: &'lines str,
+    lines: &'lines LineTable<'lines>,
+    line_index: usize,
 }
 
+impl<'lines> Iterator for FileLinesIter<'lines> {
+    type Item = (usize, Range<usize>, &'lines str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (line_number, range) = match self.lines {
+            LineTable::Ranges(lines) => lines.get(self.line_index)?.clone(),
+            LineTable::Starts(line_starts) => {
+                let start = *line_starts.get(self.line_index)?;
+                let end =
+                    (line_starts.get(self.line_index + 1).cloned()).unwrap_or(self.source.len());
+                (self.line_index + 1, start..end)
+            }
+        };
+        self.line_index += 1;
+
+        Some((line_number, range.clone(), &self.source[range]))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = match self.lines {
+            LineTable::Ranges(lines) => lines.len(),
+            LineTable::Starts(line_starts) => line_starts.len(),
+        };
+        let remaining = len.saturating_sub(self.line_index);
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for FileLinesIter<'_> {}
+
 /// A user-facing location in a source file.
 ///
 /// Returned by [`Files::location`].
@@ -173,10 +384,14 @@ pub trait Files<'a> {
 /// [`Files::location`]: Files::location
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Location {
+    /// The byte index in the source of the file.
+    pub byte_index: usize,
     /// The user-facing line number.
     pub line_number: usize,
     /// The user-facing column number.
     pub column_number: usize,
+    /// The byte index of the start of the line in the source of the file.
+    pub line_start_byte: usize,
 }
 
 /// The column index at the given byte index in the source file.
@@ -213,6 +428,11 @@ pub fn column_index(source: &str, line_range: Range<usize>, byte_index: usize) -
 
 /// Return the starting byte index of each line in the source string.
 ///
+/// Only `\n` is treated as a line terminator, so `\r\n` line endings are
+/// supported as well. Other characters that some platforms treat as line
+/// breaks, such as form feeds (`\x0C`) and vertical tabs (`\x0B`), are kept
+/// on the line they appear on.
+///
 /// This can make it easier to implement [`Files::line_index`] by allowing
 /// implementors of [`Files`] to pre-compute the line starts, then search for
 /// the corresponding line range, as shown in the example below.
@@ -294,6 +514,17 @@ where
         &self.source
     }
 
+    /// Return an estimate of the number of bytes of memory used by the file,
+    /// including its name, its source, and its cached line starts.
+    ///
+    /// The source is counted even if it is borrowed rather than owned.
+    pub fn estimated_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + display_len(&self.name)
+            + self.source.as_ref().len()
+            + self.line_starts.capacity() * std::mem::size_of::<usize>()
+    }
+
     /// Return the starting byte index of the line with the specified line index.
     /// Convenience method that already generates errors if necessary.
     fn line_start(&self, line_index: usize) -> Result<usize, Error> {
@@ -344,6 +575,36 @@ where
 
         Ok(line_start..next_line_start)
     }
+
+    fn lines(&'a self, (): ()) -> Result<FileLines<'a, &'a str>, Error> {
+        Ok(FileLines::from_line_starts(
+            self.source.as_ref(),
+            &self.line_starts,
+        ))
+    }
+
+    fn file_ids(&'a self) -> Box<dyn 'a + Iterator<Item = ()>> {
+        Box::new(std::iter::once(()))
+    }
+}
+
+/// The number of bytes in the formatted representation of a value, computed
+/// without allocating.
+fn display_len(value: &impl std::fmt::Display) -> usize {
+    use std::fmt::Write;
+
+    struct Counter(usize);
+
+    impl Write for Counter {
+        fn write_str(&mut self, s: &str) -> std::fmt::Result {
+            self.0 += s.len();
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    write!(counter, "{}", value).expect("counting bytes does not fail");
+    counter.0
 }
 
 /// A file database that can store multiple source files.
@@ -351,9 +612,12 @@ where
 /// This is useful for simple language tests, but it might be worth creating a
 /// custom implementation when a language scales beyond a certain size.
 /// It is a glorified `Vec<SimpleFile>` that implements the `Files` trait.
+///
+/// Files can be removed from the database to free up memory. File ids are not
+/// reused, so the ids of removed files stay invalid.
 #[derive(Debug, Default, Clone)]
 pub struct SimpleFiles<Name, Source> {
-    files: Vec<SimpleFile<Name, Source>>,
+    files: Vec<Option<SimpleFile<Name, Source>>>,
 }
 
 impl<Name, Source> SimpleFiles<Name, Source>
@@ -370,13 +634,96 @@ where
     /// refer to it again.
     pub fn add(&mut self, name: Name, source: Source) -> usize {
         let file_id = self.files.len();
-        self.files.push(SimpleFile::new(name, source));
+        self.files.push(Some(SimpleFile::new(name, source)));
         file_id
     }
 
     /// Get the file corresponding to the given id.
     pub fn get(&self, file_id: usize) -> Result<&SimpleFile<Name, Source>, Error> {
-        self.files.get(file_id).ok_or(Error::FileMissing)
+        match self.files.get(file_id) {
+            Some(Some(file)) => Ok(file),
+            Some(None) | None => Err(Error::FileMissing),
+        }
+    }
+
+    /// Remove the file corresponding to the given id from the database,
+    /// returning it if it was present.
+    pub fn remove(&mut self, file_id: usize) -> Option<SimpleFile<Name, Source>> {
+        self.files.get_mut(file_id).and_then(Option::take)
+    }
+
+    /// Remove all of the files from the database.
+    pub fn clear(&mut self) {
+        for file in &mut self.files {
+            *file = None;
+        }
+    }
+
+    /// Return an estimate of the number of bytes of memory used by the
+    /// database, including the names, sources, and cached line starts of
+    /// its files.
+    ///
+    /// See [`SimpleFile::estimated_size`] for details.
+    pub fn estimated_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.files.capacity() * std::mem::size_of::<Option<SimpleFile<Name, Source>>>()
+            + self
+                .files
+                .iter()
+                .flatten()
+                .map(|file| file.estimated_size() - std::mem::size_of::<SimpleFile<Name, Source>>())
+                .sum::<usize>()
+    }
+}
+
+#[cfg(feature = "fs")]
+impl SimpleFiles<String, String> {
+    /// Create a files database from the files under the `root` directory whose
+    /// extension is one of `extensions`, returning the database along with the
+    /// path and file id of each file that was added.
+    ///
+    /// Subdirectories are searched recursively, and the entries of each
+    /// directory are added in order of their paths. Symbolic links to
+    /// directories are not followed. The name of each file is its path,
+    /// including `root`.
+    ///
+    /// This requires the `fs` feature.
+    pub fn from_dir(
+        root: impl AsRef<Path>,
+        extensions: &[&str],
+    ) -> io::Result<(Self, Vec<(PathBuf, usize)>)> {
+        let mut files = SimpleFiles::new();
+        let mut paths = Vec::new();
+
+        let mut directories = vec![root.as_ref().to_path_buf()];
+        while let Some(directory) = directories.pop() {
+            let mut entries = std::fs::read_dir(&directory)?.collect::<io::Result<Vec<_>>>()?;
+            entries.sort_by_key(|entry| entry.path());
+
+            // Subdirectories are pushed in reverse, so that they are popped in order.
+            let mut subdirectories = Vec::new();
+            for entry in entries {
+                let path = entry.path();
+                if entry.file_type()?.is_dir() {
+                    subdirectories.push(path);
+                    continue;
+                }
+
+                let has_extension = match path.extension().and_then(|extension| extension.to_str())
+                {
+                    Some(extension) => extensions.contains(&extension),
+                    None => false,
+                };
+                if has_extension {
+                    let source = std::fs::read_to_string(&path)?;
+                    let file_id = files.add(path.display().to_string(), source);
+                    paths.push((path, file_id));
+                }
+            }
+            directories.extend(subdirectories.into_iter().rev());
+        }
+
+        Ok((files, paths))
     }
 }
 
@@ -404,6 +751,465 @@ where
     fn line_range(&self, file_id: usize, line_index: usize) -> Result<Range<usize>, Error> {
         self.get(file_id)?.line_range((), line_index)
     }
+
+    fn lines(&'a self, file_id: usize) -> Result<FileLines<'a, &'a str>, Error> {
+        self.get(file_id)?.lines(())
+    }
+
+    fn file_ids(&'a self) -> Box<dyn 'a + Iterator<Item = usize>> {
+        Box::new(
+            self.files
+                .iter()
+                .enumerate()
+                .filter(|(_, file)| file.is_some())
+                .map(|(file_id, _)| file_id),
+        )
+    }
+}
+
+/// A file database of static source files, that are referred to by their names.
+///
+/// This is useful for languages with a small, fixed set of built-in source
+/// files, such as a prelude, where using the name of the file as a [`FileId`]
+/// is more convenient than keeping track of numeric ids. Files are looked up
+/// with a linear search, so this is not intended for large numbers of files.
+///
+/// [`FileId`]: Files::FileId
+#[derive(Debug, Default, Clone)]
+pub struct StaticFiles {
+    files: Vec<SimpleFile<&'static str, &'static str>>,
+}
+
+impl StaticFiles {
+    /// Create a new files database.
+    pub fn new() -> StaticFiles {
+        StaticFiles { files: Vec::new() }
+    }
+
+    /// Add a file to the database, returning its name, which can be used to
+    /// refer to it again. If a file with the same name was already added, it
+    /// is replaced.
+    pub fn add(&mut self, name: &'static str, source: &'static str) -> &'static str {
+        let file = SimpleFile::new(name, source);
+        match self.files.iter_mut().find(|file| *file.name() == name) {
+            Some(existing) => *existing = file,
+            None => self.files.push(file),
+        }
+        name
+    }
+
+    /// Get the file with the given name.
+    pub fn get(&self, name: &str) -> Result<&SimpleFile<&'static str, &'static str>, Error> {
+        self.files
+            .iter()
+            .find(|file| *file.name() == name)
+            .ok_or(Error::FileMissing)
+    }
+}
+
+impl<'a> Files<'a> for StaticFiles {
+    type FileId = &'static str;
+    type Name = &'static str;
+    type Source = &'static str;
+
+    fn name(&self, name: &'static str) -> Result<&'static str, Error> {
+        Ok(*self.get(name)?.name())
+    }
+
+    fn source(&self, name: &'static str) -> Result<&'static str, Error> {
+        Ok(*self.get(name)?.source())
+    }
+
+    fn line_index(&self, name: &'static str, byte_index: usize) -> Result<usize, Error> {
+        self.get(name)?.line_index((), byte_index)
+    }
+
+    fn line_range(&self, name: &'static str, line_index: usize) -> Result<Range<usize>, Error> {
+        self.get(name)?.line_range((), line_index)
+    }
+
+    fn lines(&'a self, name: &'static str) -> Result<FileLines<'a, &'static str>, Error> {
+        let file = self.get(name)?;
+        Ok(FileLines::from_line_starts(
+            *file.source(),
+            &file.line_starts,
+        ))
+    }
+
+    fn file_ids(&'a self) -> Box<dyn 'a + Iterator<Item = &'static str>> {
+        Box::new(self.files.iter().map(|file| *file.name()))
+    }
+}
+
+/// A file database that loads files on demand, by calling a closure that
+/// returns the name and source of the file with a given id.
+///
+/// The closure is only called once for each id, the first time it is looked
+/// up, and the result is then cached. If the closure returns `None`, the file
+/// is reported as missing by this and any later lookups. Files are looked up
+/// with a linear search over the cached ids, so this is not intended for large
+/// numbers of files.
+///
+/// ```rust
+/// use codespan_reporting::files::{ClosureFiles, Files};
+///
+/// let files = ClosureFiles::new(|id: usize| match id {
+///     0 => Some((String::from("main.rs"), String::from("fn main() {}\n"))),
+///     _ => None,
+/// });
+///
+/// assert_eq!(files.name(0).unwrap().as_ref(), "main.rs");
+/// assert!(files.name(1).is_err());
+/// ```
+pub struct ClosureFiles<FileId, F> {
+    load: F,
+    /// The files that were looked up, which are `None` if the closure did
+    /// not return a file.
+    files: RefCell<Vec<(FileId, Option<LoadedFile>)>>,
+}
+
+/// A file loaded by [`ClosureFiles`].
+type LoadedFile = Rc<SimpleFile<Rc<str>, Rc<str>>>;
+
+impl<FileId, F> ClosureFiles<FileId, F>
+where
+    FileId: Copy + PartialEq,
+    F: Fn(FileId) -> Option<(String, String)>,
+{
+    /// Create a new files database that loads files with the given closure.
+    pub fn new(load: F) -> ClosureFiles<FileId, F> {
+        ClosureFiles {
+            load,
+            files: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Get the file with the given id, loading it if it was not looked up yet.
+    fn get(&self, id: FileId) -> Result<LoadedFile, Error> {
+        let loaded_file = self
+            .files
+            .borrow()
+            .iter()
+            .find(|(file_id, _)| *file_id == id)
+            .map(|(_, file)| file.clone());
+
+        let file = match loaded_file {
+            Some(file) => file,
+            None => {
+                let file = (self.load)(id).map(|(name, source)| {
+                    Rc::new(SimpleFile::new(Rc::from(name), Rc::from(source)))
+                });
+                self.files.borrow_mut().push((id, file.clone()));
+                file
+            }
+        };
+        file.ok_or(Error::FileMissing)
+    }
+}
+
+impl<FileId, F> std::fmt::Debug for ClosureFiles<FileId, F>
+where
+    FileId: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClosureFiles")
+            .field("files", &self.files)
+            .finish()
+    }
+}
+
+impl<'a, FileId, F> Files<'a> for ClosureFiles<FileId, F>
+where
+    FileId: 'a + Copy + PartialEq,
+    F: Fn(FileId) -> Option<(String, String)>,
+{
+    type FileId = FileId;
+    type Name = Rc<str>;
+    type Source = Rc<str>;
+
+    fn name(&self, id: FileId) -> Result<Rc<str>, Error> {
+        Ok(self.get(id)?.name().clone())
+    }
+
+    fn source(&self, id: FileId) -> Result<Rc<str>, Error> {
+        Ok(self.get(id)?.source().clone())
+    }
+
+    fn line_index(&self, id: FileId, byte_index: usize) -> Result<usize, Error> {
+        self.get(id)?.line_index((), byte_index)
+    }
+
+    fn line_range(&self, id: FileId, line_index: usize) -> Result<Range<usize>, Error> {
+        self.get(id)?.line_range((), line_index)
+    }
+}
+
+/// A fragment of a larger host file, such as a snippet of an embedded language
+/// inside a string literal, that starts at a byte offset in the host file.
+///
+/// Diagnostics about the fragment can be built with ranges that are relative
+/// to the start of the fragment, and then translated into diagnostics about
+/// the host file with [`SubFile::label`] or [`SubFile::diagnostic`]. The
+/// translated diagnostics are rendered within the context of the host file,
+/// with the line and column numbers of the host file. The sub-file implements
+/// [`Files`] by delegating to the host database, so it can be passed to the
+/// renderer in place of the host database.
+///
+/// ```rust
+/// use codespan_reporting::diagnostic::{Diagnostic, Label};
+/// use codespan_reporting::files::{SimpleFiles, SubFile};
+///
+/// let mut files = SimpleFiles::new();
+/// let file_id = files.add("query.rs", r#"let query = "SELECT * FORM users";"#);
+///
+/// // The fragment starts after the opening quote.
+/// let fragment = SubFile::new(&files, file_id, 13);
+/// let diagnostic = fragment.diagnostic(
+///     Diagnostic::error()
+///         .with_message("expected `FROM`")
+///         .with_labels(vec![Label::primary((), 9..13)]),
+/// );
+/// assert_eq!(diagnostic.labels[0].range, 22..26);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SubFile<'a, DB: Files<'a>> {
+    files: &'a DB,
+    file_id: DB::FileId,
+    offset: usize,
+}
+
+impl<'a, DB: Files<'a>> SubFile<'a, DB> {
+    /// Create a fragment of the file with the given id in the host database,
+    /// starting at the byte index `offset` of the file.
+    pub fn new(files: &'a DB, file_id: DB::FileId, offset: usize) -> SubFile<'a, DB> {
+        SubFile {
+            files,
+            file_id,
+            offset,
+        }
+    }
+
+    /// Translate a range relative to the start of the fragment into a range in
+    /// the host file. Offsets that would overflow saturate at `usize::MAX`.
+    pub fn host_range(&self, range: Range<usize>) -> Range<usize> {
+        range.start.saturating_add(self.offset)..range.end.saturating_add(self.offset)
+    }
+
+    /// Translate a label about the fragment into a label about the host file.
+    pub fn label(&self, label: Label<()>) -> Label<DB::FileId> {
+        // The ranges of synthetic lines are not positioned in the fragment.
+        if label.synthetic_line.is_some() {
+            return label.with_file(self.file_id);
+        }
+        let range = self.host_range(label.range.clone());
+        Label {
+            range,
+            ..label.with_file(self.file_id)
+        }
+    }
+
+    /// Translate a diagnostic about the fragment into a diagnostic about the
+    /// host file, by calling [`SubFile::label`] on each of its labels, and
+    /// translating the ranges of the replacements of its suggestions.
+    pub fn diagnostic(&self, diagnostic: Diagnostic<()>) -> Diagnostic<DB::FileId> {
+        Diagnostic {
+            severity: diagnostic.severity,
+            code: diagnostic.code,
+            message: diagnostic.message,
+            labels: diagnostic
+                .labels
+                .into_iter()
+                .map(|label| self.label(label))
+                .collect(),
+            notes: diagnostic.notes,
+            steps: diagnostic
+                .steps
+                .into_iter()
+                .map(|(label, description)| (self.label(label), description))
+                .collect(),
+            suggestions: diagnostic
+                .suggestions
+                .into_iter()
+                .map(|suggestion| Suggestion {
+                    message: suggestion.message,
+                    replacements: suggestion
+                        .replacements
+                        .into_iter()
+                        .map(|(_, range, text)| (self.file_id, self.host_range(range), text))
+                        .collect(),
+                    applicability: suggestion.applicability,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<'a, DB: Files<'a>> Files<'a> for SubFile<'a, DB> {
+    type FileId = DB::FileId;
+    type Name = DB::Name;
+    type Source = DB::Source;
+
+    fn name(&'a self, id: DB::FileId) -> Result<DB::Name, Error> {
+        self.files.name(id)
+    }
+
+    fn source(&'a self, id: DB::FileId) -> Result<DB::Source, Error> {
+        self.files.source(id)
+    }
+
+    fn line_index(&'a self, id: DB::FileId, byte_index: usize) -> Result<usize, Error> {
+        self.files.line_index(id, byte_index)
+    }
+
+    fn line_number(&'a self, id: DB::FileId, line_index: usize) -> Result<usize, Error> {
+        self.files.line_number(id, line_index)
+    }
+
+    fn column_number(
+        &'a self,
+        id: DB::FileId,
+        line_index: usize,
+        byte_index: usize,
+    ) -> Result<usize, Error> {
+        self.files.column_number(id, line_index, byte_index)
+    }
+
+    fn line_range(&'a self, id: DB::FileId, line_index: usize) -> Result<Range<usize>, Error> {
+        self.files.line_range(id, line_index)
+    }
+
+    fn lines(&'a self, id: DB::FileId) -> Result<FileLines<'a, DB::Source>, Error> {
+        self.files.lines(id)
+    }
+
+    fn file_ids(&'a self) -> Box<dyn 'a + Iterator<Item = DB::FileId>> {
+        self.files.file_ids()
+    }
+}
+
+/// A file database that normalizes the names of the files of another database.
+///
+/// Names are converted to Unicode Normalization Form C, and are optionally
+/// lowercased, so that names that only differ in their normalization form or
+/// their case are treated as the same name, like on case-insensitive file
+/// systems. The normalized names are used for display and for looking up files
+/// by name with [`NormalizedNameFiles::find`], while the sources of the files
+/// are left untouched.
+///
+/// This requires the `normalize-names` feature.
+///
+/// ```rust
+/// use codespan_reporting::files::{Files, NormalizedNameFiles, SimpleFiles};
+///
+/// let mut files = SimpleFiles::new();
+/// let file_id = files.add("Main.rs", "fn main() {}");
+///
+/// let files = NormalizedNameFiles::new(files).with_case_folding(true);
+/// assert_eq!(files.name(file_id).unwrap(), "main.rs");
+/// assert_eq!(files.find("MAIN.rs"), Some(file_id));
+/// ```
+#[cfg(feature = "normalize-names")]
+#[derive(Debug, Clone)]
+pub struct NormalizedNameFiles<DB> {
+    files: DB,
+    case_folding: bool,
+}
+
+#[cfg(feature = "normalize-names")]
+impl<DB> NormalizedNameFiles<DB> {
+    /// Wrap a file database, normalizing the names of its files.
+    pub fn new(files: DB) -> NormalizedNameFiles<DB> {
+        NormalizedNameFiles {
+            files,
+            case_folding: false,
+        }
+    }
+
+    /// Set whether file names are lowercased as well.
+    pub fn with_case_folding(mut self, case_folding: bool) -> NormalizedNameFiles<DB> {
+        self.case_folding = case_folding;
+        self
+    }
+
+    /// Normalize a file name.
+    pub fn normalize_name(&self, name: &str) -> String {
+        if self.case_folding {
+            name.to_lowercase().nfc().collect()
+        } else {
+            name.nfc().collect()
+        }
+    }
+
+    /// Get a reference to the wrapped file database.
+    pub fn get_ref(&self) -> &DB {
+        &self.files
+    }
+
+    /// Unwrap the wrapped file database.
+    pub fn into_inner(self) -> DB {
+        self.files
+    }
+}
+
+#[cfg(feature = "normalize-names")]
+impl<'a, DB: Files<'a>> NormalizedNameFiles<DB> {
+    /// Find the first file whose normalized name is the same as the
+    /// normalized version of the given name.
+    ///
+    /// This returns `None` if the wrapped database does not implement
+    /// [`Files::file_ids`].
+    pub fn find(&'a self, name: &str) -> Option<DB::FileId> {
+        let name = self.normalize_name(name);
+        self.files.file_ids().find(|id| match self.files.name(*id) {
+            Ok(file_name) => self.normalize_name(&file_name.to_string()) == name,
+            Err(_) => false,
+        })
+    }
+}
+
+#[cfg(feature = "normalize-names")]
+impl<'a, DB: Files<'a>> Files<'a> for NormalizedNameFiles<DB> {
+    type FileId = DB::FileId;
+    type Name = String;
+    type Source = DB::Source;
+
+    fn name(&'a self, id: DB::FileId) -> Result<String, Error> {
+        let name = self.files.name(id)?;
+        Ok(self.normalize_name(&name.to_string()))
+    }
+
+    fn source(&'a self, id: DB::FileId) -> Result<DB::Source, Error> {
+        self.files.source(id)
+    }
+
+    fn line_index(&'a self, id: DB::FileId, byte_index: usize) -> Result<usize, Error> {
+        self.files.line_index(id, byte_index)
+    }
+
+    fn line_number(&'a self, id: DB::FileId, line_index: usize) -> Result<usize, Error> {
+        self.files.line_number(id, line_index)
+    }
+
+    fn column_number(
+        &'a self,
+        id: DB::FileId,
+        line_index: usize,
+        byte_index: usize,
+    ) -> Result<usize, Error> {
+        self.files.column_number(id, line_index, byte_index)
+    }
+
+    fn line_range(&'a self, id: DB::FileId, line_index: usize) -> Result<Range<usize>, Error> {
+        self.files.line_range(id, line_index)
+    }
+
+    fn lines(&'a self, id: DB::FileId) -> Result<FileLines<'a, DB::Source>, Error> {
+        self.files.lines(id)
+    }
+
+    fn file_ids(&'a self) -> Box<dyn 'a + Iterator<Item = DB::FileId>> {
+        self.files.file_ids()
+    }
 }
 
 #[cfg(test)]
@@ -440,4 +1246,298 @@ mod test {
 
         assert_eq!(line_sources, ["foo\n", "bar\r\n", "\n", "baz"]);
     }
+
+    #[test]
+    fn line_starts_form_feed() {
+        let file = SimpleFile::new("test", "foo\x0Cbar\x0Bbaz\nqux\n");
+
+        assert_eq!(file.line_starts, [0, 12, 16]);
+        assert_eq!(file.line_index((), 8).unwrap(), 0);
+        assert_eq!(
+            &file.source[file.line_range((), 0).unwrap()],
+            "foo\x0Cbar\x0Bbaz\n",
+        );
+    }
+
+    #[test]
+    fn utf16_range() {
+        let file = SimpleFile::new("test", "a\n🗻∈🌏 b\n");
+
+        assert_eq!(file.utf16_range((), 0..1).unwrap(), 0..1);
+        // Each of the mountain and the globe is a surrogate pair, while `∈` is
+        // in the basic multilingual plane.
+        assert_eq!(file.utf16_range((), 2..6).unwrap(), 2..4);
+        assert_eq!(file.utf16_range((), 2..13).unwrap(), 2..7);
+        assert_eq!(file.utf16_range((), 14..15).unwrap(), 8..9);
+        assert!(matches!(
+            file.utf16_range((), 2..3),
+            Err(Error::InvalidCharBoundary { given: 3 }),
+        ));
+        assert!(matches!(
+            file.utf16_range((), 0..20),
+            Err(Error::IndexTooLarge { given: 20, max: 16 }),
+        ));
+    }
+
+    #[test]
+    fn closure_files() {
+        let loaded = RefCell::new(Vec::new());
+        let files = ClosureFiles::new(|id: usize| {
+            loaded.borrow_mut().push(id);
+            match id {
+                0 | 1 => Some((
+                    format!("file{}.fun", id),
+                    format!("let x{} =\n  {};\n", id, id),
+                )),
+                _ => None,
+            }
+        });
+
+        assert_eq!(files.name(1).unwrap().as_ref(), "file1.fun");
+        assert_eq!(files.name(0).unwrap().as_ref(), "file0.fun");
+        assert_eq!(files.location(1, 11).unwrap().line_number, 2);
+        assert_eq!(
+            &files.source(0).unwrap()[files.line_range(0, 1).unwrap()],
+            "  0;\n"
+        );
+        assert!(matches!(files.name(2), Err(Error::FileMissing)));
+        assert!(matches!(files.source(2), Err(Error::FileMissing)));
+        assert_eq!(*loaded.borrow(), [1, 0, 2]);
+
+        let diagnostic = Diagnostic::error()
+            .with_message("unexpected integer")
+            .with_labels(vec![Label::primary(1, 11..12)]);
+        let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+        crate::term::emit(&mut writer, &Default::default(), &files, &diagnostic).unwrap();
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            concat!(
+                "error: unexpected integer\n",
+                "  ┌─ file1.fun:2:3\n",
+                "  │\n",
+                "2 │   1;\n",
+                "  │   ^\n",
+                "\n",
+            ),
+        );
+        assert_eq!(*loaded.borrow(), [1, 0, 2]);
+    }
+
+    #[test]
+    fn lines() {
+        let file = SimpleFile::new("test", TEST_SOURCE);
+
+        assert_eq!(
+            file.lines(()).unwrap().iter().collect::<Vec<_>>(),
+            [
+                (1, 0..4, "foo\n"),
+                (2, 4..9, "bar\r\n"),
+                (3, 9..10, "\n"),
+                (4, 10..13, "baz"),
+            ],
+        );
+        assert_eq!(file.lines(()).unwrap().len(), 4);
+        assert_eq!(file.lines(()).unwrap().iter().len(), 4);
+
+        let mut files = SimpleFiles::new();
+        let id = files.add("test", TEST_SOURCE);
+        let mut static_files = StaticFiles::new();
+        static_files.add("test", TEST_SOURCE);
+        let file_lines = file.lines(()).unwrap();
+        let expected = file_lines.iter().collect::<Vec<_>>();
+        assert_eq!(
+            files.lines(id).unwrap().iter().collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(
+            static_files
+                .lines("test")
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>(),
+            expected,
+        );
+
+        // A database that only implements the required methods uses the
+        // default implementation, which looks up each line range.
+        struct Unindexed<'a>(&'a SimpleFile<&'static str, &'static str>);
+
+        impl<'a> Files<'a> for Unindexed<'a> {
+            type FileId = ();
+            type Name = &'static str;
+            type Source = &'a str;
+
+            fn name(&'a self, (): ()) -> Result<&'static str, Error> {
+                Files::name(self.0, ())
+            }
+
+            fn source(&'a self, (): ()) -> Result<&'a str, Error> {
+                Files::source(self.0, ())
+            }
+
+            fn line_index(&'a self, (): (), byte_index: usize) -> Result<usize, Error> {
+                Files::line_index(self.0, (), byte_index)
+            }
+
+            fn line_range(&'a self, (): (), line_index: usize) -> Result<Range<usize>, Error> {
+                Files::line_range(self.0, (), line_index)
+            }
+        }
+
+        let unindexed = Unindexed(&file);
+        assert_eq!(unindexed.lines(()).unwrap().len(), 4);
+        assert_eq!(
+            unindexed.lines(()).unwrap().iter().collect::<Vec<_>>(),
+            expected,
+        );
+    }
+
+    #[test]
+    fn sub_file_host_range() {
+        let file = SimpleFile::new("test", TEST_SOURCE);
+        let fragment = SubFile::new(&file, (), 4);
+
+        assert_eq!(fragment.host_range(0..3), 4..7);
+        assert_eq!(fragment.host_range(1..usize::MAX), 5..usize::MAX);
+    }
+
+    #[test]
+    fn location() {
+        let file = SimpleFile::new("test", TEST_SOURCE);
+
+        assert_eq!(
+            file.location((), 6).unwrap(),
+            Location {
+                byte_index: 6,
+                line_number: 2,
+                column_number: 3,
+                line_start_byte: 4,
+            },
+        );
+    }
+
+    #[test]
+    fn file_ids() {
+        let mut files = SimpleFiles::new();
+        let ids = vec![
+            files.add("foo", TEST_SOURCE),
+            files.add("bar", TEST_SOURCE),
+            files.add("baz", TEST_SOURCE),
+        ];
+
+        assert_eq!(files.file_ids().collect::<Vec<_>>(), ids);
+
+        let file = SimpleFile::new("test", TEST_SOURCE);
+        assert_eq!(file.file_ids().collect::<Vec<_>>(), [()]);
+    }
+
+    #[cfg(feature = "normalize-names")]
+    #[test]
+    fn normalized_name_files() {
+        let mut files = SimpleFiles::new();
+        let upper = files.add("Foo.rs", TEST_SOURCE);
+        let lower = files.add("foo.rs", TEST_SOURCE);
+        let decomposed = files.add("caf\u{65}\u{301}.rs", TEST_SOURCE);
+
+        let files = NormalizedNameFiles::new(files);
+        assert_eq!(files.name(upper).unwrap(), "Foo.rs");
+        assert_eq!(files.name(decomposed).unwrap(), "caf\u{e9}.rs");
+        assert_eq!(files.find("foo.rs"), Some(lower));
+        assert_eq!(files.find("caf\u{e9}.rs"), Some(decomposed));
+
+        let files = files.with_case_folding(true);
+        assert_eq!(files.name(upper).unwrap(), files.name(lower).unwrap());
+        assert_eq!(files.find("foo.rs"), Some(upper));
+        assert_eq!(files.find("FOO.RS"), Some(upper));
+        assert_eq!(files.source(lower).unwrap(), TEST_SOURCE);
+    }
+
+    #[test]
+    fn static_files() {
+        let mut files = StaticFiles::new();
+        let prelude = files.add("prelude", "fn id(x) = x\n");
+        let intrinsics = files.add("intrinsics", "extern fn add\nextern fn sub\n");
+
+        assert_eq!(
+            files.file_ids().collect::<Vec<_>>(),
+            ["prelude", "intrinsics"]
+        );
+        assert_eq!(files.line_index(intrinsics, 14).unwrap(), 1);
+        assert_eq!(files.line_range(intrinsics, 1).unwrap(), 14..28);
+        assert_eq!(files.source(prelude).unwrap(), "fn id(x) = x\n");
+        assert!(matches!(files.source("missing"), Err(Error::FileMissing)));
+
+        files.add("prelude", "fn const(x, y) = x\n");
+        assert_eq!(files.file_ids().count(), 2);
+        assert_eq!(files.source(prelude).unwrap(), "fn const(x, y) = x\n");
+    }
+
+    #[test]
+    fn remove_and_clear() {
+        let mut files = SimpleFiles::new();
+        let foo = files.add("foo", TEST_SOURCE);
+        let bar = files.add("bar", TEST_SOURCE);
+
+        assert_eq!(files.remove(foo).map(|file| *file.name()), Some("foo"));
+        assert!(files.remove(foo).is_none());
+        assert!(matches!(files.get(foo), Err(Error::FileMissing)));
+        assert_eq!(files.file_ids().collect::<Vec<_>>(), [bar]);
+
+        let baz = files.add("baz", TEST_SOURCE);
+        assert_ne!(baz, foo);
+
+        files.clear();
+        assert_eq!(files.file_ids().count(), 0);
+        assert!(matches!(files.source(bar), Err(Error::FileMissing)));
+    }
+
+    #[test]
+    fn estimated_size() {
+        let mut files = SimpleFiles::new();
+        let empty_size = files.estimated_size();
+
+        let source = "a\n".repeat(1000);
+        files.add("a.txt".to_owned(), source.clone());
+        files.add("b.txt".to_owned(), source.clone());
+
+        // Two sources, two names, and 1001 line starts per file.
+        let content_size = 2 * (source.len() + "a.txt".len() + 1001 * std::mem::size_of::<usize>());
+        let size = files.estimated_size() - empty_size;
+        assert!(size >= content_size, "{} < {}", size, content_size);
+        assert!(size <= content_size * 2, "{} > {}", size, content_size * 2);
+
+        let file_id = files.add("c.txt".to_owned(), String::new());
+        let size = files.estimated_size();
+        files.remove(file_id);
+        assert!(files.estimated_size() < size);
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn from_dir() {
+        let root = std::env::temp_dir().join(format!("codespan-from-dir-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("src/nested")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(root.join("src/nested/lib.rs"), "pub fn foo() {}\n").unwrap();
+        std::fs::write(root.join("src/notes.txt"), "not rust\n").unwrap();
+        std::fs::write(root.join("build.rs"), "fn main() {}\n").unwrap();
+
+        let result = SimpleFiles::from_dir(&root, &["rs"]);
+        std::fs::remove_dir_all(&root).unwrap();
+        let (files, paths) = result.unwrap();
+
+        let expected_paths = [
+            root.join("build.rs"),
+            root.join("src/main.rs"),
+            root.join("src/nested/lib.rs"),
+        ];
+        assert_eq!(
+            paths.iter().map(|(path, _)| path).collect::<Vec<_>>(),
+            expected_paths.iter().collect::<Vec<_>>(),
+        );
+        for (path, file_id) in &paths {
+            assert_eq!(files.name(*file_id).unwrap(), path.display().to_string());
+        }
+        assert_eq!(files.source(paths[2].1).unwrap(), "pub fn foo() {}\n");
+    }
 }
@@ -1,18 +1,26 @@
 //! Terminal back-end for emitting diagnostics.
 
+use std::collections::BTreeMap;
 use std::str::FromStr;
 use termcolor::{ColorChoice, WriteColor};
 
-use crate::diagnostic::Diagnostic;
+use crate::diagnostic::{Diagnostic, Label, LabelStyle, Severity};
 use crate::files::Files;
 
 mod config;
+mod passes;
 mod renderer;
+mod resolved;
+mod sink;
 mod views;
 
 pub use termcolor;
 
-pub use self::config::{Chars, Config, DisplayStyle, Styles};
+pub use self::config::{
+    Chars, Config, DisplayStyle, GutterAlign, RequirePrimary, SeverityCase, Styles,
+};
+pub use self::resolved::ResolvedLocation;
+pub use self::sink::{StyledSink, TermcolorSink};
 
 /// A command line argument that configures the coloring of the output.
 ///
@@ -78,8 +86,139 @@ impl From<ColorArg> for ColorChoice {
     }
 }
 
+/// A summary of the output of an emitted diagnostic.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct EmitSummary {
+    /// The number of bytes that were written to the writer, excluding any bytes
+    /// written by changing colors.
+    pub bytes_written: usize,
+    /// The number of emitted diagnostics with a severity of [`Severity::Bug`].
+    pub bugs: usize,
+    /// The number of emitted diagnostics with a severity of [`Severity::Error`].
+    pub errors: usize,
+    /// The number of emitted diagnostics with a severity of [`Severity::Warning`].
+    pub warnings: usize,
+    /// The number of emitted diagnostics with a severity of [`Severity::Note`].
+    pub notes: usize,
+    /// The number of emitted diagnostics with a severity of [`Severity::Help`].
+    pub helps: usize,
+}
+
+impl EmitSummary {
+    /// The number of emitted diagnostics with the given severity.
+    pub fn count(&self, severity: Severity) -> usize {
+        match severity {
+            Severity::Bug => self.bugs,
+            Severity::Error => self.errors,
+            Severity::Warning => self.warnings,
+            Severity::Note => self.notes,
+            Severity::Help => self.helps,
+        }
+    }
+
+    fn count_mut(&mut self, severity: Severity) -> &mut usize {
+        match severity {
+            Severity::Bug => &mut self.bugs,
+            Severity::Error => &mut self.errors,
+            Severity::Warning => &mut self.warnings,
+            Severity::Note => &mut self.notes,
+            Severity::Help => &mut self.helps,
+        }
+    }
+}
+
+impl std::ops::AddAssign for EmitSummary {
+    fn add_assign(&mut self, other: EmitSummary) {
+        self.bytes_written += other.bytes_written;
+        self.bugs += other.bugs;
+        self.errors += other.errors;
+        self.warnings += other.warnings;
+        self.notes += other.notes;
+        self.helps += other.helps;
+    }
+}
+
+/// A sink that counts the number of bytes written to it.
+struct CountingSink<'sink> {
+    sink: &'sink mut dyn StyledSink,
+    bytes_written: usize,
+}
+
+impl StyledSink for CountingSink<'_> {
+    fn write_str(&mut self, text: &str) -> std::io::Result<()> {
+        self.sink.write_str(text)?;
+        self.bytes_written += text.len();
+        Ok(())
+    }
+
+    fn set_style(&mut self, spec: &termcolor::ColorSpec) -> std::io::Result<()> {
+        self.sink.set_style(spec)
+    }
+
+    fn reset(&mut self) -> std::io::Result<()> {
+        self.sink.reset()
+    }
+}
+
+/// A sink that holds back newlines until more content is written, so that
+/// trailing newlines are never written.
+struct TrimNewlinesSink<'sink> {
+    sink: &'sink mut dyn StyledSink,
+    pending_newlines: usize,
+}
+
+impl StyledSink for TrimNewlinesSink<'_> {
+    fn write_str(&mut self, text: &str) -> std::io::Result<()> {
+        let content = text.trim_end_matches('\n');
+        if content.is_empty() {
+            self.pending_newlines += text.len();
+        } else {
+            while self.pending_newlines > 0 {
+                self.sink.write_str("\n")?;
+                self.pending_newlines -= 1;
+            }
+            self.sink.write_str(content)?;
+            self.pending_newlines = text.len() - content.len();
+        }
+        Ok(())
+    }
+
+    fn set_style(&mut self, spec: &termcolor::ColorSpec) -> std::io::Result<()> {
+        self.sink.set_style(spec)
+    }
+
+    fn reset(&mut self) -> std::io::Result<()> {
+        self.sink.reset()
+    }
+}
+
+/// A sink that writes the text to both an ANSI-styled and a plain buffer.
+struct DualSink {
+    ansi: termcolor::Ansi<Vec<u8>>,
+    plain: String,
+}
+
+impl StyledSink for DualSink {
+    fn write_str(&mut self, text: &str) -> std::io::Result<()> {
+        std::io::Write::write_all(&mut self.ansi, text.as_bytes())?;
+        self.plain.push_str(text);
+        Ok(())
+    }
+
+    fn set_style(&mut self, spec: &termcolor::ColorSpec) -> std::io::Result<()> {
+        self.ansi.set_color(spec)
+    }
+
+    fn reset(&mut self) -> std::io::Result<()> {
+        self.ansi.reset()
+    }
+}
+
 /// Emit a diagnostic using the given writer, context, config, and files.
 ///
+/// On success, a summary of the output is returned, which can be used to keep
+/// track of the amount of output across many diagnostics.
+///
 /// The return value covers all error cases. These error case can arise if:
 /// * a file was removed from the file database.
 /// * a file was changed so that it is too small to have an index
@@ -89,16 +228,917 @@ pub fn emit<'files, F: Files<'files>>(
     config: &Config,
     files: &'files F,
     diagnostic: &Diagnostic<F::FileId>,
+) -> Result<EmitSummary, super::files::Error> {
+    emit_to_sink(&mut TermcolorSink::new(writer), config, files, diagnostic)
+}
+
+/// Emit a diagnostic to the given [`StyledSink`], for styling back-ends that
+/// do not implement [`WriteColor`]. This is otherwise the same as [`emit`].
+pub fn emit_to_sink<'files, F: Files<'files>>(
+    sink: &mut dyn StyledSink,
+    config: &Config,
+    files: &'files F,
+    diagnostic: &Diagnostic<F::FileId>,
+) -> Result<EmitSummary, super::files::Error> {
+    emit_indexed(sink, config, files, diagnostic, None)
+}
+
+/// Emit a diagnostic to the given [`StyledSink`], with its index in a batch
+/// and the size of the batch, for [`Config::show_index`].
+fn emit_indexed<'files, F: Files<'files>>(
+    sink: &mut dyn StyledSink,
+    config: &Config,
+    files: &'files F,
+    diagnostic: &Diagnostic<F::FileId>,
+    index: Option<(usize, usize)>,
+) -> Result<EmitSummary, super::files::Error> {
+    let mut sink = CountingSink {
+        sink,
+        bytes_written: 0,
+    };
+    if config.trailing_newline {
+        render(&mut sink, config, files, diagnostic, index)?;
+    } else {
+        let mut sink = TrimNewlinesSink {
+            sink: &mut sink,
+            pending_newlines: 0,
+        };
+        render(&mut sink, config, files, diagnostic, index)?;
+    }
+
+    let mut summary = EmitSummary {
+        bytes_written: sink.bytes_written,
+        ..EmitSummary::default()
+    };
+    *summary.count_mut(diagnostic.severity) += 1;
+
+    Ok(summary)
+}
+
+/// Render some output other than a diagnostic to the given writer, returning
+/// a summary with the number of bytes that were written.
+fn emit_counted(
+    writer: &mut dyn WriteColor,
+    render: impl FnOnce(&mut dyn StyledSink) -> Result<(), super::files::Error>,
+) -> Result<EmitSummary, super::files::Error> {
+    let mut sink = TermcolorSink::new(writer);
+    let mut sink = CountingSink {
+        sink: &mut sink,
+        bytes_written: 0,
+    };
+    render(&mut sink)?;
+
+    Ok(EmitSummary {
+        bytes_written: sink.bytes_written,
+        ..EmitSummary::default()
+    })
+}
+
+/// Options for rendering an individual diagnostic of a batch, as returned by
+/// the filter passed to [`emit_all_with`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Whether to render the notes of the diagnostic.
+    pub show_notes: bool,
+    /// Whether to render the secondary labels of the diagnostic.
+    pub show_secondary_labels: bool,
+    /// The maximum number of labels to render, if any. The labels that were
+    /// given first are rendered.
+    pub max_labels: Option<usize>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions {
+            show_notes: true,
+            show_secondary_labels: true,
+            max_labels: None,
+        }
+    }
+}
+
+/// Emit a batch of diagnostics in the order they were given in.
+///
+/// If [`Config::dedup_repeated_notes`] is enabled, notes that were already
+/// emitted earlier in the batch are abbreviated to `(same as above)`.
+/// If [`Config::show_index`] is enabled, each diagnostic is prefixed with its
+/// index in the batch, such as `[2/3]`.
+///
+/// The returned summary covers the output of all of the diagnostics.
+pub fn emit_all<'files, F: Files<'files>>(
+    writer: &mut dyn WriteColor,
+    config: &Config,
+    files: &'files F,
+    diagnostics: &[Diagnostic<F::FileId>],
+) -> Result<EmitSummary, super::files::Error> {
+    emit_all_with(writer, config, files, diagnostics, |_| {
+        RenderOptions::default()
+    })
+}
+
+/// Emit a batch of diagnostics like [`emit_all`], with the parts of each
+/// diagnostic that are rendered chosen by the given filter.
+pub fn emit_all_with<'files, F: Files<'files>>(
+    writer: &mut dyn WriteColor,
+    config: &Config,
+    files: &'files F,
+    diagnostics: &[Diagnostic<F::FileId>],
+    filter: impl Fn(&Diagnostic<F::FileId>) -> RenderOptions,
+) -> Result<EmitSummary, super::files::Error> {
+    let mut summary = EmitSummary::default();
+    let mut seen_notes = Vec::new();
+    for (index, diagnostic) in diagnostics.iter().enumerate() {
+        let filtered_diagnostic;
+        let options = filter(diagnostic);
+        let diagnostic = if options == RenderOptions::default() {
+            diagnostic
+        } else {
+            let mut diagnostic = diagnostic.clone();
+            if !options.show_notes {
+                diagnostic.notes.clear();
+            }
+            if !options.show_secondary_labels {
+                diagnostic
+                    .labels
+                    .retain(|label| label.style == LabelStyle::Primary);
+            }
+            if let Some(max_labels) = options.max_labels {
+                diagnostic.labels.truncate(max_labels);
+            }
+            filtered_diagnostic = diagnostic;
+            &filtered_diagnostic
+        };
+
+        let deduplicated_diagnostic;
+        let diagnostic = if config.dedup_repeated_notes {
+            let mut diagnostic = diagnostic.clone();
+            for note in &mut diagnostic.notes {
+                if seen_notes.contains(note) {
+                    *note = String::from("(same as above)");
+                } else {
+                    seen_notes.push(note.clone());
+                }
+            }
+            deduplicated_diagnostic = diagnostic;
+            &deduplicated_diagnostic
+        } else {
+            diagnostic
+        };
+
+        summary += emit_indexed(
+            &mut TermcolorSink::new(&mut *writer),
+            config,
+            files,
+            diagnostic,
+            Some((index, diagnostics.len())),
+        )?;
+    }
+
+    Ok(summary)
+}
+
+/// Return the value of a result, or emit its error as a diagnostic.
+///
+/// This is useful for the common pattern of computing a value and reporting
+/// the error if that fails, for error types that can be converted into a
+/// [`Diagnostic`]. If the diagnostic could not be emitted, that error is
+/// returned instead.
+///
+/// ```rust
+/// use codespan_reporting::diagnostic::Diagnostic;
+/// use codespan_reporting::files::SimpleFiles;
+/// use codespan_reporting::term::{self, termcolor::NoColor, Config};
+///
+/// struct ParseError(String);
+///
+/// impl From<ParseError> for Diagnostic<usize> {
+///     fn from(error: ParseError) -> Diagnostic<usize> {
+///         Diagnostic::error().with_message(error.0)
+///     }
+/// }
+///
+/// let files = SimpleFiles::<&str, &str>::new();
+/// let mut writer = NoColor::new(Vec::new());
+/// let result = Err::<u32, _>(ParseError(String::from("oh no")));
+///
+/// let value = term::report(&mut writer, &Config::default(), &files, result).unwrap();
+/// assert_eq!(value, None);
+/// ```
+pub fn report<'files, F, T, E>(
+    writer: &mut dyn WriteColor,
+    config: &Config,
+    files: &'files F,
+    result: Result<T, E>,
+) -> Result<Option<T>, super::files::Error>
+where
+    F: Files<'files>,
+    E: Into<Diagnostic<F::FileId>>,
+{
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(error) => {
+            emit(writer, config, files, &error.into())?;
+            Ok(None)
+        }
+    }
+}
+
+/// Emit a standalone line summarizing the number of diagnostics of each
+/// severity, such as `2 errors, 3 warnings`.
+///
+/// The summary can be accumulated across several calls to [`emit`] or
+/// [`emit_all`], so that it can be printed once at the end. The numbers of
+/// errors and warnings are always shown, while the other severities are only
+/// shown if any diagnostics of that severity were emitted.
+///
+/// The returned summary only counts the bytes of the line, and no diagnostics.
+pub fn emit_summary(
+    writer: &mut dyn WriteColor,
+    config: &Config,
+    summary: &EmitSummary,
+) -> Result<EmitSummary, super::files::Error> {
+    let counts = [
+        Severity::Bug,
+        Severity::Error,
+        Severity::Warning,
+        Severity::Note,
+        Severity::Help,
+    ]
+    .iter()
+    .map(|&severity| (severity, summary.count(severity)))
+    .filter(|&(severity, count)| {
+        count > 0 || severity == Severity::Error || severity == Severity::Warning
+    })
+    .collect::<Vec<_>>();
+
+    emit_counted(writer, |sink| {
+        renderer::Renderer::new(sink, config).render_summary(&counts)
+    })
+}
+
+fn render<'files, F: Files<'files>>(
+    sink: &mut dyn StyledSink,
+    config: &Config,
+    files: &'files F,
+    diagnostic: &Diagnostic<F::FileId>,
+    index: Option<(usize, usize)>,
 ) -> Result<(), super::files::Error> {
+    use self::passes;
     use self::renderer::Renderer;
-    use self::views::{RichDiagnostic, ShortDiagnostic};
+    use self::views::{
+        CompactDiagnostic, ElmDiagnostic, NarrowDiagnostic, RichDiagnostic, ShortDiagnostic,
+    };
+
+    let diagnostic = passes::promote_first_label(config, diagnostic.clone());
+    let diagnostic = passes::rebase_offsets(config, diagnostic);
+    let focus = passes::focused_range(config, &diagnostic);
+    let diagnostic = passes::remove_synthetic_lines(config, diagnostic);
+    let diagnostic = passes::skip_empty_notes(config, diagnostic);
+    let diagnostic = passes::describe_empty_message(config, diagnostic);
+    let diagnostic = passes::link_severity_help(config, diagnostic);
+    let diagnostic = passes::merge_adjacent_labels(config, diagnostic);
+    let diagnostic = passes::qualify_uncertain_labels(config, diagnostic);
+    let diagnostic = passes::annotate_caret_columns(config, files, diagnostic)?;
+    let diagnostic = passes::coalesce_identical_labels(config, diagnostic);
+    let diagnostic = passes::fold_secondary_labels(config, files, diagnostic)?;
+    let diagnostic = &passes::cap_snippet_blocks(config, files, diagnostic)?;
+
+    let mut renderer = Renderer::new(sink, config);
+    renderer.set_index(index);
+
+    if passes::needs_min_width_fallback(config, files, diagnostic, &renderer)? {
+        return ShortDiagnostic::new(diagnostic, false).render(files, &mut renderer);
+    }
 
-    let mut renderer = Renderer::new(writer, config);
     match config.display_style {
-        DisplayStyle::Rich => RichDiagnostic::new(diagnostic, config).render(files, &mut renderer),
+        DisplayStyle::Rich => RichDiagnostic::new(diagnostic, config)
+            .with_focus(focus)
+            .render(files, &mut renderer),
+        DisplayStyle::Compact => match CompactDiagnostic::new(diagnostic, config, files)? {
+            Some(compact) => compact.render(files, &mut renderer),
+            None => RichDiagnostic::new(diagnostic, config)
+                .with_focus(focus)
+                .render(files, &mut renderer),
+        },
+        DisplayStyle::Narrow => {
+            NarrowDiagnostic::new(diagnostic, config).render(files, &mut renderer)
+        }
         DisplayStyle::Medium => ShortDiagnostic::new(diagnostic, true).render(files, &mut renderer),
         DisplayStyle::Short => ShortDiagnostic::new(diagnostic, false).render(files, &mut renderer),
+        DisplayStyle::Elm => ElmDiagnostic::new(diagnostic, config).render(files, &mut renderer),
+    }
+}
+
+/// Emit a batch of diagnostics, grouped by severity.
+///
+/// Diagnostics are emitted in order of descending severity, starting with bugs
+/// and errors, followed by warnings, notes, and help messages. Diagnostics with
+/// the same severity are emitted in the order they were given in. Each group is
+/// preceded by a section header, styled with the header style of the group's
+/// severity.
+///
+/// The returned summary covers the output of all of the diagnostics and of
+/// the section headers.
+pub fn emit_by_severity<'files, F: Files<'files>>(
+    writer: &mut dyn WriteColor,
+    config: &Config,
+    files: &'files F,
+    diagnostics: &[Diagnostic<F::FileId>],
+) -> Result<EmitSummary, super::files::Error> {
+    use self::renderer::Renderer;
+
+    let mut diagnostics = diagnostics.iter().collect::<Vec<_>>();
+    // `sort_by_key` is stable, so the order within each group is preserved.
+    diagnostics.sort_by_key(|diagnostic| std::cmp::Reverse(diagnostic.severity));
+
+    let mut summary = EmitSummary::default();
+    let mut current_severity = None;
+    for diagnostic in diagnostics {
+        if current_severity != Some(diagnostic.severity) {
+            current_severity = Some(diagnostic.severity);
+            summary += emit_counted(writer, |sink| {
+                Renderer::new(sink, config).render_section_header(diagnostic.severity)
+            })?;
+        }
+        summary += emit(writer, config, files, diagnostic)?;
+    }
+
+    Ok(summary)
+}
+
+/// Render a diagnostic both with ANSI escape codes and as plain text, returning
+/// the ANSI output followed by the plain output.
+///
+/// The diagnostic is only laid out once, so this is cheaper than emitting it
+/// twice, such as for showing colored output interactively while logging plain
+/// text. The plain output is the same as the ANSI output without its escape
+/// codes.
+pub fn render_both<'files, F: Files<'files>>(
+    config: &Config,
+    files: &'files F,
+    diagnostic: &Diagnostic<F::FileId>,
+) -> Result<(String, String), super::files::Error> {
+    let mut sink = DualSink {
+        ansi: termcolor::Ansi::new(Vec::new()),
+        plain: String::new(),
+    };
+    emit_to_sink(&mut sink, config, files, diagnostic)?;
+
+    let ansi =
+        String::from_utf8(sink.ansi.into_inner()).expect("rendered diagnostics are valid UTF-8");
+    Ok((ansi, sink.plain))
+}
+
+/// Render a batch of diagnostics without colors, grouped by severity.
+///
+/// Each severity that occurs in the batch is mapped to the rendered output of
+/// its diagnostics, in the order they were given in. This is useful for
+/// showing each severity separately, such as in the tabs of a user interface.
+pub fn render_by_severity<'files, F: Files<'files>>(
+    config: &Config,
+    files: &'files F,
+    diagnostics: &[Diagnostic<F::FileId>],
+) -> Result<BTreeMap<Severity, String>, super::files::Error> {
+    let mut writers = BTreeMap::new();
+    for diagnostic in diagnostics {
+        let writer = writers
+            .entry(diagnostic.severity)
+            .or_insert_with(|| termcolor::NoColor::new(Vec::<u8>::new()));
+        emit(writer, config, files, diagnostic)?;
+    }
+
+    Ok(writers
+        .into_iter()
+        .map(|(severity, writer)| {
+            let output = String::from_utf8(writer.into_inner())
+                .expect("rendered diagnostics are valid UTF-8");
+            (severity, output)
+        })
+        .collect())
+}
+
+/// Emit only the most severe diagnostic of each file, as a summary of a batch.
+///
+/// Diagnostics are grouped by the file of their first primary label, and the
+/// groups are emitted in the order that their files first appear in. For each
+/// file, the diagnostic with the highest severity is emitted, with ties broken
+/// in favour of the diagnostic that was given first. If other diagnostics were
+/// suppressed, a note of the form `(+N more in this file)` is appended to it.
+///
+/// Diagnostics without a primary label are not associated with a file, and are
+/// emitted as they are.
+///
+/// The returned summary only counts the diagnostics that were emitted, and not
+/// the suppressed ones.
+pub fn emit_top_per_file<'files, F: Files<'files>>(
+    writer: &mut dyn WriteColor,
+    config: &Config,
+    files: &'files F,
+    diagnostics: &[Diagnostic<F::FileId>],
+) -> Result<EmitSummary, super::files::Error> {
+    use crate::diagnostic::LabelStyle;
+
+    enum Group<'diagnostic, FileId> {
+        File {
+            file_id: FileId,
+            top: &'diagnostic Diagnostic<FileId>,
+            suppressed: usize,
+        },
+        Unlabelled(&'diagnostic Diagnostic<FileId>),
+    }
+
+    let mut groups = Vec::<Group<'_, F::FileId>>::new();
+    for diagnostic in diagnostics {
+        let primary_file_id = diagnostic
+            .labels
+            .iter()
+            .find(|label| label.style == LabelStyle::Primary)
+            .map(|label| label.file_id);
+
+        let primary_file_id = match primary_file_id {
+            Some(primary_file_id) => primary_file_id,
+            None => {
+                groups.push(Group::Unlabelled(diagnostic));
+                continue;
+            }
+        };
+
+        let group = groups.iter_mut().find_map(|group| match group {
+            Group::File {
+                file_id,
+                top,
+                suppressed,
+            } if *file_id == primary_file_id => Some((top, suppressed)),
+            Group::File { .. } | Group::Unlabelled(_) => None,
+        });
+
+        match group {
+            Some((top, suppressed)) => {
+                if diagnostic.severity > top.severity {
+                    *top = diagnostic;
+                }
+                *suppressed += 1;
+            }
+            None => groups.push(Group::File {
+                file_id: primary_file_id,
+                top: diagnostic,
+                suppressed: 0,
+            }),
+        }
+    }
+
+    let mut summary = EmitSummary::default();
+    for group in groups {
+        summary += match group {
+            Group::File {
+                top, suppressed: 0, ..
+            }
+            | Group::Unlabelled(top) => emit(writer, config, files, top)?,
+            Group::File {
+                top, suppressed, ..
+            } => {
+                let top = top
+                    .clone()
+                    .with_note(format!("(+{} more in this file)", suppressed));
+                emit(writer, config, files, &top)?
+            }
+        };
+    }
+
+    Ok(summary)
+}
+
+/// Emit the source code of two labels side by side, for comparing them.
+///
+/// The width of the terminal is split into two columns separated by a divider,
+/// with the lines covered by the `left` label rendered in the left column, and
+/// the lines covered by the `right` label rendered in the right column. Lines
+/// that are too long for their column are clipped. The terminal width is taken
+/// from [`Config::terminal_width`], and defaults to 80 columns.
+///
+/// Labels are styled as they would be in a note. The returned summary only
+/// counts the bytes that were written, as no diagnostic is emitted.
+pub fn emit_side_by_side<'files, F: Files<'files>>(
+    writer: &mut dyn WriteColor,
+    config: &Config,
+    files: &'files F,
+    left: &Label<F::FileId>,
+    right: &Label<F::FileId>,
+) -> Result<EmitSummary, super::files::Error> {
+    use self::renderer::Renderer;
+    use self::views::SideBySideDiagnostic;
+
+    emit_counted(writer, |sink| {
+        let mut renderer = Renderer::new(sink, config);
+        SideBySideDiagnostic::new(left, right, config).render(files, &mut renderer)
+    })
+}
+
+/// Emit the whole of a file, with the labels of the given diagnostics overlaid
+/// on its source code, such as for an annotated view of a file in a review tool.
+///
+/// The message of each label is tagged with the severity of its diagnostic,
+/// and falls back to the message of the diagnostic if the label has none. The
+/// label carets are styled with the most severe diagnostic on each line.
+/// Labels in other files are ignored, and multi-line labels are shown up to
+/// the end of their first line.
+///
+/// The returned summary counts the diagnostics that have labels in the file.
+pub fn emit_annotated_file<'files, F: Files<'files>>(
+    writer: &mut dyn WriteColor,
+    config: &Config,
+    files: &'files F,
+    file_id: F::FileId,
+    diagnostics: &[Diagnostic<F::FileId>],
+) -> Result<EmitSummary, super::files::Error> {
+    use self::renderer::Renderer;
+    use self::views::AnnotatedFile;
+
+    let mut summary = emit_counted(writer, |sink| {
+        let mut renderer = Renderer::new(sink, config);
+        AnnotatedFile::new(file_id, diagnostics, config).render(files, &mut renderer)
+    })?;
+    for diagnostic in diagnostics {
+        if diagnostic
+            .labels
+            .iter()
+            .any(|label| label.file_id == file_id)
+        {
+            *summary.count_mut(diagnostic.severity) += 1;
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Emit a diagnostic whose labels have already been resolved to lines of source.
+///
+/// This is an escape hatch for tools that maintain their own position indexes,
+/// allowing the renderer to skip looking up line indices and line ranges via a
+/// [`Files`] database. Each label carries a [`ResolvedLocation`] in place of a
+/// file id, and its range is relative to the start of the resolved line.
+///
+/// The output matches that of [`emit`] if the resolved locations are accurate,
+/// with the following exceptions, which are all because the text of the
+/// unlabelled lines is not known:
+///
+/// * [`Config::before_label_lines`] and [`Config::after_label_lines`] are
+///   ignored.
+/// * A single unlabelled line between two labelled lines is rendered without
+///   its text.
+/// * Labels that span multiple lines are rendered up to the end of their
+///   start line, because their range is clamped to the end of the resolved
+///   line.
+///
+/// [`Files`]: crate::files::Files
+pub fn emit_resolved(
+    writer: &mut dyn WriteColor,
+    config: &Config,
+    diagnostic: &Diagnostic<ResolvedLocation<'_>>,
+) -> Result<EmitSummary, super::files::Error> {
+    use self::resolved::ResolvedFiles;
+
+    let config = Config {
+        before_label_lines: 0,
+        after_label_lines: 0,
+        ..config.clone()
+    };
+    let (files, diagnostic) = ResolvedFiles::new(diagnostic);
+
+    emit(writer, &config, &files, &diagnostic)
+}
+
+/// The resolved location of a label, for tools that display diagnostics in a
+/// structured form, such as the problems panel of an editor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serialization",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct LabelLocation {
+    /// The style of the label.
+    pub style: LabelStyle,
+    /// The user-facing name of the file of the label.
+    pub file_name: String,
+    /// The user-facing line number of the start of the label.
+    pub line: usize,
+    /// The user-facing column number of the start of the label.
+    pub column: usize,
+    /// The message of the label.
+    pub message: String,
+    /// The tag of the label.
+    pub tag: Option<u64>,
+}
+
+/// Resolve the locations of the labels of a diagnostic, in the order that the
+/// labels were given in, without rendering the diagnostic.
+///
+/// Labels with a display column are located at that column.
+pub fn label_locations<'files, F: Files<'files>>(
+    files: &'files F,
+    diagnostic: &Diagnostic<F::FileId>,
+) -> Result<Vec<LabelLocation>, super::files::Error> {
+    diagnostic
+        .labels
+        .iter()
+        .map(|label| {
+            let location = views::label_location(files, label)?;
+            Ok(LabelLocation {
+                style: label.style,
+                file_name: files.name(label.file_id)?.to_string(),
+                line: location.line_number,
+                column: location.column_number,
+                message: label.message.clone(),
+                tag: label.tag,
+            })
+        })
+        .collect()
+}
+
+/// Emit the labels of a batch of diagnostics in a machine-readable format, for
+/// editor quickfix lists and tools like `grep`.
+///
+/// Each label is written on its own line, without color, in the format
+/// `file:line:column:severity:code:message`, where the code is empty if the
+/// diagnostic has none, and the message is the message of the diagnostic
+/// followed by the message of the label, if it has one. Line breaks in
+/// messages are replaced with spaces, so that each label stays on a single
+/// line. The lines are sorted by file name and then by position, with labels at
+/// the same position written in the order they were given in.
+pub fn emit_quickfix<'files, F: Files<'files>>(
+    writer: &mut dyn WriteColor,
+    config: &Config,
+    files: &'files F,
+    diagnostics: &[Diagnostic<F::FileId>],
+) -> Result<(), super::files::Error> {
+    let mut lines = Vec::new();
+    for diagnostic in diagnostics {
+        for location in label_locations(files, diagnostic)? {
+            let message = if location.message.is_empty() {
+                single_line(&diagnostic.message)
+            } else {
+                format!(
+                    "{}: {}",
+                    single_line(&diagnostic.message),
+                    single_line(&location.message),
+                )
+            };
+            let file_name = if location.file_name.is_empty() {
+                config.anonymous_file_name.clone()
+            } else {
+                location.file_name
+            };
+            lines.push((
+                file_name,
+                location.line,
+                location.column,
+                diagnostic,
+                message,
+            ));
+        }
+    }
+    lines.sort_by(|a, b| (&a.0, a.1, a.2).cmp(&(&b.0, b.1, b.2)));
+
+    for (file_name, line, column, diagnostic, message) in lines {
+        writeln!(
+            writer,
+            "{}:{}:{}:{}:{}:{}",
+            file_name,
+            line,
+            column,
+            renderer::severity_name(diagnostic.severity),
+            diagnostic.code.as_deref().unwrap_or(""),
+            message,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Emit a batch of diagnostics as single lines for syslog, without color.
+///
+/// Each primary label is written on its own line, in the format
+/// `<priority>file:line:column: message`, where the priority is the
+/// [RFC 5424] severity of the diagnostic:
+///
+/// | Severity              | Priority            |
+/// |-----------------------|---------------------|
+/// | [`Severity::Bug`]     | `2` (critical)      |
+/// | [`Severity::Error`]   | `3` (error)         |
+/// | [`Severity::Warning`] | `4` (warning)       |
+/// | [`Severity::Note`]    | `5` (notice)        |
+/// | [`Severity::Help`]    | `6` (informational) |
+///
+/// No facility is included in the priority, so that the lines can be written
+/// to the standard error of a service managed by systemd, or passed to a
+/// logger that adds the facility itself. Diagnostics without primary labels
+/// are written as `<priority>message`. Line breaks in messages are replaced
+/// with spaces, so that each entry stays on a single line.
+///
+/// [RFC 5424]: https://datatracker.ietf.org/doc/html/rfc5424#section-6.2.1
+pub fn emit_syslog<'files, F: Files<'files>>(
+    writer: &mut dyn WriteColor,
+    config: &Config,
+    files: &'files F,
+    diagnostics: &[Diagnostic<F::FileId>],
+) -> Result<(), super::files::Error> {
+    for diagnostic in diagnostics {
+        let priority = syslog_priority(diagnostic.severity);
+        let message = single_line(&diagnostic.message);
+
+        let locations = label_locations(files, diagnostic)?
+            .into_iter()
+            .filter(|location| location.style == LabelStyle::Primary)
+            .collect::<Vec<_>>();
+        if locations.is_empty() {
+            writeln!(writer, "<{}>{}", priority, message)?;
+        }
+        for location in locations {
+            let file_name = if location.file_name.is_empty() {
+                &config.anonymous_file_name
+            } else {
+                &location.file_name
+            };
+            writeln!(
+                writer,
+                "<{}>{}:{}:{}: {}",
+                priority, file_name, location.line, location.column, message,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Join the lines of a message with spaces, for formats that write each entry on
+/// a single line.
+fn single_line(message: &str) -> String {
+    message
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The RFC 5424 severity of a diagnostic, used by [`emit_syslog`].
+fn syslog_priority(severity: Severity) -> u8 {
+    match severity {
+        Severity::Bug => 2,
+        Severity::Error => 3,
+        Severity::Warning => 4,
+        Severity::Note => 5,
+        Severity::Help => 6,
+    }
+}
+
+/// Emit a batch of diagnostics as an aligned table, with one row for each
+/// diagnostic and columns for the severity, code, location, and message.
+///
+/// ```text
+/// severity  code   location     message
+/// error     E0308  main.rs:2:9  mismatched types
+/// warning          main.rs:1:5  unused variable: `x`
+/// ```
+///
+/// The location is that of the first primary label, and is empty for
+/// diagnostics without one. No source snippets or notes are shown, and line
+/// breaks in messages are replaced with spaces, so that each row stays on a
+/// single line. If [`Config::terminal_width`] is set, messages that do not fit
+/// are truncated with the [`Chars::source_clip`] marker.
+pub fn emit_table<'files, F: Files<'files>>(
+    writer: &mut dyn WriteColor,
+    config: &Config,
+    files: &'files F,
+    diagnostics: &[Diagnostic<F::FileId>],
+) -> Result<(), super::files::Error> {
+    use unicode_width::UnicodeWidthStr;
+
+    const COLUMN_GAP: usize = 2;
+
+    let mut rows = Vec::with_capacity(diagnostics.len());
+    for diagnostic in diagnostics {
+        let location = label_locations(files, diagnostic)?
+            .into_iter()
+            .find(|location| location.style == LabelStyle::Primary);
+        let location = match location {
+            Some(location) if location.file_name.is_empty() => format!(
+                "{}:{}:{}",
+                config.anonymous_file_name, location.line, location.column,
+            ),
+            Some(location) => format!(
+                "{}:{}:{}",
+                location.file_name, location.line, location.column,
+            ),
+            None => String::new(),
+        };
+        rows.push((
+            diagnostic.severity,
+            [
+                renderer::severity_name(diagnostic.severity).to_owned(),
+                diagnostic.code.clone().unwrap_or_default(),
+                location,
+                single_line(&diagnostic.message),
+            ],
+        ));
+    }
+
+    let header = ["severity", "code", "location", "message"];
+    let mut widths = [0; 3];
+    for (index, width) in widths.iter_mut().enumerate() {
+        let cells = rows.iter().map(|(_, cells)| cells[index].as_str());
+        *width = std::iter::once(header[index])
+            .chain(cells)
+            .map(|cell| cell.width())
+            .max()
+            .unwrap_or(0);
+    }
+
+    // Truncate the messages to the space that is left over by the other columns.
+    if let Some(terminal_width) = config.terminal_width {
+        let used_width = widths.iter().map(|width| width + COLUMN_GAP).sum::<usize>();
+        let message_width = terminal_width.saturating_sub(used_width);
+        for (_, cells) in &mut rows {
+            cells[3] = truncate_to_width(&cells[3], message_width, &config.chars.source_clip);
+        }
+    }
+
+    let mut sink = TermcolorSink::new(writer);
+    let mut renderer = renderer::Renderer::new(&mut sink, config);
+    renderer.render_table_row(&widths, COLUMN_GAP, None, &header)?;
+    for (severity, cells) in &rows {
+        let cells = [
+            cells[0].as_str(),
+            cells[1].as_str(),
+            cells[2].as_str(),
+            cells[3].as_str(),
+        ];
+        renderer.render_table_row(&widths, COLUMN_GAP, Some(*severity), &cells)?;
     }
+
+    Ok(())
+}
+
+/// Truncate some text to the given display width, ending it with the clip
+/// marker if it had to be truncated.
+fn truncate_to_width(text: &str, width: usize, clip: &str) -> String {
+    use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+    if text.width() <= width {
+        return text.to_owned();
+    }
+
+    let mut truncated = String::new();
+    let mut truncated_width = 0;
+    let available_width = width.saturating_sub(clip.width());
+    for ch in text.chars() {
+        truncated_width += ch.width().unwrap_or(0);
+        if truncated_width > available_width {
+            break;
+        }
+        truncated.push(ch);
+    }
+    truncated.truncate(truncated.trim_end().len());
+    truncated.push_str(clip);
+    truncated
+}
+
+/// A diagnostic with resolved label locations, as written by [`emit_jsonl`].
+#[cfg(feature = "json")]
+#[derive(serde::Serialize)]
+struct JsonDiagnostic<'a> {
+    severity: &'static str,
+    code: Option<&'a str>,
+    message: &'a str,
+    labels: Vec<LabelLocation>,
+    notes: &'a [String],
+}
+
+/// Emit a batch of diagnostics as newline-delimited JSON, for streaming into
+/// log pipelines and tools like `jq`.
+///
+/// Each diagnostic is written as a compact JSON object on its own line, with
+/// its labels resolved to file names, line numbers, and column numbers like
+/// [`label_locations`].
+#[cfg(feature = "json")]
+pub fn emit_jsonl<'files, F: Files<'files>>(
+    writer: &mut dyn WriteColor,
+    files: &'files F,
+    diagnostics: &[Diagnostic<F::FileId>],
+) -> Result<(), super::files::Error> {
+    for diagnostic in diagnostics {
+        let json_diagnostic = JsonDiagnostic {
+            severity: renderer::severity_name(diagnostic.severity),
+            code: diagnostic.code.as_deref(),
+            message: &diagnostic.message,
+            labels: label_locations(files, diagnostic)?,
+            notes: &diagnostic.notes,
+        };
+        serde_json::to_writer(&mut *writer, &json_diagnostic).map_err(std::io::Error::from)?;
+        writeln!(writer)?;
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -106,7 +1146,7 @@ mod tests {
     use super::*;
 
     use crate::diagnostic::Label;
-    use crate::files::SimpleFiles;
+    use crate::files::{SimpleFile, SimpleFiles};
 
     #[test]
     fn unsized_emit() {
@@ -118,4 +1158,866 @@ mod tests {
 
         emit(&mut writer, &Config::default(), &files, &diagnostic).unwrap();
     }
+
+    #[test]
+    fn emit_summary() {
+        let mut files = SimpleFiles::new();
+        let id = files.add("test", "let x = 1;\nlet y = x +;\n");
+        let diagnostics = vec![
+            Diagnostic::error()
+                .with_message("oh no")
+                .with_labels(vec![Label::primary(id, 21..22)]),
+            Diagnostic::warning().with_message("careful"),
+        ];
+
+        let mut writer = termcolor::Ansi::new(Vec::<u8>::new());
+        let summary = emit(&mut writer, &Config::default(), &files, &diagnostics[0]).unwrap();
+        let mut plain_writer = termcolor::NoColor::new(Vec::<u8>::new());
+        let plain_summary = emit(
+            &mut plain_writer,
+            &Config::default(),
+            &files,
+            &diagnostics[0],
+        )
+        .unwrap();
+        assert_eq!(summary.bytes_written, plain_writer.get_ref().len());
+        assert_eq!(summary, plain_summary);
+
+        let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+        let summary = emit_all(&mut writer, &Config::default(), &files, &diagnostics).unwrap();
+        assert_eq!(summary.bytes_written, writer.get_ref().len());
+    }
+
+    #[test]
+    fn label_locations() {
+        let mut files = SimpleFiles::new();
+        let main_id = files.add("main.rs", "fn main() {\n    foo(1, 2);\n}\n");
+        let lib_id = files.add("lib.rs", "pub fn foo(x: u32) {}\n");
+        let diagnostic = Diagnostic::error().with_labels(vec![
+            Label::primary(main_id, 16..25)
+                .with_message("expected 1 argument")
+                .with_tag(42),
+            Label::secondary(lib_id, 7..10).with_message("defined here"),
+        ]);
+
+        assert_eq!(
+            super::label_locations(&files, &diagnostic).unwrap(),
+            vec![
+                LabelLocation {
+                    style: LabelStyle::Primary,
+                    file_name: "main.rs".to_owned(),
+                    line: 2,
+                    column: 5,
+                    message: "expected 1 argument".to_owned(),
+                    tag: Some(42),
+                },
+                LabelLocation {
+                    style: LabelStyle::Secondary,
+                    file_name: "lib.rs".to_owned(),
+                    line: 1,
+                    column: 8,
+                    message: "defined here".to_owned(),
+                    tag: None,
+                },
+            ],
+        );
+    }
+
+    #[test]
+    fn preserve_tabs_in_output() {
+        let mut files = SimpleFiles::new();
+        let id = files.add("test", "\tlet x = 1;\n");
+        let diagnostic = Diagnostic::error().with_labels(vec![Label::primary(id, 5..6)]);
+
+        let config = Config {
+            preserve_tabs_in_output: true,
+            ..Config::default()
+        };
+        let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+        emit(&mut writer, &config, &files, &diagnostic).unwrap();
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+
+        assert!(output.contains("1 │ \tlet x = 1;\n"), "{:?}", output);
+        assert!(output.contains("  │         ^\n"), "{:?}", output);
+    }
+
+    #[test]
+    fn quickfix() {
+        let mut files = SimpleFiles::new();
+        let main_id = files.add("main.rs", "fn main() {\n    foo(1, 2);\n}\n");
+        let lib_id = files.add("lib.rs", "pub fn foo(x: u32) {}\n");
+        let diagnostics = vec![
+            Diagnostic::error()
+                .with_code("E0061")
+                .with_message("this function takes 1 argument but 2 arguments were supplied")
+                .with_labels(vec![
+                    Label::primary(main_id, 16..25).with_message("expected 1 argument"),
+                    Label::secondary(lib_id, 7..10),
+                ]),
+            Diagnostic::warning()
+                .with_message("unused argument")
+                .with_labels(vec![Label::primary(lib_id, 11..12)]),
+        ];
+
+        let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+        emit_quickfix(&mut writer, &Config::default(), &files, &diagnostics).unwrap();
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+
+        assert_eq!(
+            output.lines().collect::<Vec<_>>(),
+            [
+                "lib.rs:1:8:error:E0061:this function takes 1 argument but 2 arguments were supplied",
+                "lib.rs:1:12:warning::unused argument",
+                "main.rs:2:5:error:E0061:this function takes 1 argument but 2 arguments were supplied: expected 1 argument",
+            ],
+        );
+        for line in output.lines() {
+            assert_eq!(line.splitn(6, ':').count(), 6);
+        }
+
+        let diagnostics = vec![Diagnostic::error()
+            .with_message("mismatched types\n  in this call")
+            .with_labels(vec![
+                Label::primary(main_id, 16..25).with_message("expected `u32`,\nfound `&str`")
+            ])];
+
+        let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+        emit_quickfix(&mut writer, &Config::default(), &files, &diagnostics).unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "main.rs:2:5:error::mismatched types in this call: expected `u32`, found `&str`\n",
+        );
+    }
+
+    #[test]
+    fn render_both() {
+        let file = SimpleFile::new("main.rs", "fn main() {\n    let x: u32 = \"one\";\n}\n");
+        let diagnostic = Diagnostic::error()
+            .with_code("E0308")
+            .with_message("mismatched types")
+            .with_labels(vec![
+                Label::primary((), 29..34).with_message("expected `u32`, found `&str`"),
+                Label::secondary((), 23..26).with_message("expected due to this"),
+            ])
+            .with_notes(vec!["expected type `u32`".to_owned()]);
+
+        let (ansi, plain) = super::render_both(&Config::default(), &file, &diagnostic).unwrap();
+
+        let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+        emit(&mut writer, &Config::default(), &file, &diagnostic).unwrap();
+        assert_eq!(plain, String::from_utf8(writer.into_inner()).unwrap());
+
+        assert_ne!(ansi, plain);
+        let mut stripped = String::new();
+        let mut chars = ansi.chars();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\x1b' => {
+                    chars.by_ref().find(|ch| *ch == 'm');
+                }
+                _ => stripped.push(ch),
+            }
+        }
+        assert_eq!(stripped, plain);
+    }
+
+    #[test]
+    fn syslog() {
+        let mut files = SimpleFiles::new();
+        let file_id = files.add("main.rs", "fn main() {\n    let x = foo(1, 2);\n}\n");
+        let diagnostics = vec![
+            Diagnostic::error()
+                .with_code("E0425")
+                .with_message("cannot find function `foo`\nin this scope")
+                .with_labels(vec![
+                    Label::primary(file_id, 24..27).with_message("not found in this scope"),
+                    Label::secondary(file_id, 3..7),
+                ]),
+            Diagnostic::warning()
+                .with_message("unused variable: `x`")
+                .with_labels(vec![Label::primary(file_id, 20..21)]),
+            Diagnostic::help().with_message("run with `--verbose` for more details"),
+        ];
+
+        let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+        emit_syslog(&mut writer, &Config::default(), &files, &diagnostics).unwrap();
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+
+        assert_eq!(
+            output,
+            concat!(
+                "<3>main.rs:2:13: cannot find function `foo` in this scope\n",
+                "<4>main.rs:2:9: unused variable: `x`\n",
+                "<6>run with `--verbose` for more details\n",
+            ),
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn jsonl() {
+        let mut files = SimpleFiles::new();
+        let file_id = files.add("main.rs", "fn main() {\n    foo(1, 2);\n}\n");
+        let diagnostics = vec![
+            Diagnostic::error()
+                .with_code("E0425")
+                .with_message("cannot find function `foo` in this scope")
+                .with_labels(vec![
+                    Label::primary(file_id, 16..19).with_message("not found in this scope")
+                ]),
+            Diagnostic::warning()
+                .with_message("unused variable")
+                .with_notes(vec!["prefix it with an underscore".to_owned()]),
+            Diagnostic::note().with_message("compiling main.rs"),
+        ];
+
+        let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+        emit_jsonl(&mut writer, &files, &diagnostics).unwrap();
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+
+        assert!(output.ends_with('\n'));
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+
+        let first = serde_json::from_str::<serde_json::Value>(lines[0]).unwrap();
+        assert_eq!(first["severity"], "error");
+        assert_eq!(first["code"], "E0425");
+        assert_eq!(first["labels"][0]["file_name"], "main.rs");
+        assert_eq!(first["labels"][0]["line"], 2);
+        assert_eq!(first["labels"][0]["column"], 5);
+
+        let second = serde_json::from_str::<serde_json::Value>(lines[1]).unwrap();
+        assert_eq!(second["code"], serde_json::Value::Null);
+        assert_eq!(second["notes"][0], "prefix it with an underscore");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn max_code_width() {
+        let file = SimpleFile::new("db.rs", "query(&format!(\"{}\", input));\n");
+        let diagnostics = vec![Diagnostic::warning()
+            .with_code("security/injection/sql")
+            .with_message("possible SQL injection")
+            .with_labels(vec![Label::primary((), 6..29)])];
+        let config = Config {
+            max_code_width: Some(12),
+            ..Config::default()
+        };
+
+        let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+        emit(&mut writer, &config, &file, &diagnostics[0]).unwrap();
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(
+            output.starts_with("warning[security/in…]: possible SQL injection\n"),
+            "{:?}",
+            output,
+        );
+
+        let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+        emit_jsonl(&mut writer, &file, &diagnostics).unwrap();
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        let json = serde_json::from_str::<serde_json::Value>(output.trim_end()).unwrap();
+        assert_eq!(json["code"], "security/injection/sql");
+    }
+
+    #[test]
+    fn config_presets() {
+        let compact = Config::compact();
+        assert!(matches!(compact.display_style, DisplayStyle::Compact));
+        assert_eq!(compact.start_context_lines, 0);
+        assert_eq!(compact.end_context_lines, 0);
+        assert!(compact.dedent_common_indent);
+        assert!(compact.inline_cross_file_refs);
+
+        let verbose = Config::verbose();
+        assert!(matches!(verbose.display_style, DisplayStyle::Rich));
+        assert!(verbose.start_context_lines > Config::default().start_context_lines);
+        assert!(verbose.end_context_lines > Config::default().end_context_lines);
+        assert_eq!(verbose.before_label_lines, 2);
+        assert_eq!(verbose.after_label_lines, 2);
+        assert!(!verbose.inline_cross_file_refs);
+    }
+
+    #[test]
+    fn trailing_newline() {
+        let mut files = SimpleFiles::new();
+        let id = files.add("test", "let x = 1;\n");
+        let diagnostic = Diagnostic::error()
+            .with_message("oh no")
+            .with_labels(vec![Label::primary(id, 4..5)]);
+
+        let emit_with = |display_style, trailing_newline| {
+            let config = Config {
+                display_style,
+                trailing_newline,
+                ..Config::default()
+            };
+            let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+            emit(&mut writer, &config, &files, &diagnostic).unwrap();
+            String::from_utf8(writer.into_inner()).unwrap()
+        };
+
+        let rich = emit_with(DisplayStyle::Rich, true);
+        assert!(rich.ends_with("^\n\n"), "{:?}", rich);
+        let rich = emit_with(DisplayStyle::Rich, false);
+        assert!(rich.ends_with(" ^"), "{:?}", rich);
+
+        let short = emit_with(DisplayStyle::Short, true);
+        assert_eq!(short, "test:1:5: error: oh no\n");
+        let short = emit_with(DisplayStyle::Short, false);
+        assert_eq!(short, "test:1:5: error: oh no");
+    }
+
+    #[test]
+    fn show_index() {
+        let mut files = SimpleFiles::new();
+        let id = files.add("test", "let x = y;\n");
+        let diagnostics = vec![
+            Diagnostic::error()
+                .with_message("unknown variable")
+                .with_labels(vec![Label::primary(id, 8..9)]),
+            Diagnostic::warning().with_message("unused variable"),
+            Diagnostic::note().with_message("compiling test"),
+        ];
+
+        let config = Config {
+            show_index: true,
+            ..Config::default()
+        };
+        let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+        let summary = emit_all(&mut writer, &config, &files, &diagnostics).unwrap();
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+
+        let headers = output
+            .lines()
+            .filter(|line| line.starts_with('['))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            headers,
+            [
+                "[1/3] error: unknown variable",
+                "[2/3] warning: unused variable",
+                "[3/3] note: compiling test",
+            ],
+        );
+        assert_eq!(summary.bytes_written, output.len());
+
+        // The index is part of the header, after the code badge.
+        let config = Config {
+            show_index: true,
+            code_badge_line: true,
+            ..Config::default()
+        };
+        let diagnostic = Diagnostic::error()
+            .with_code("E0425")
+            .with_message("unknown variable");
+        let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+        emit_all(&mut writer, &config, &files, &[diagnostic]).unwrap();
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(
+            output.starts_with("┌─ E0425 ─┐\n[1/1] error: unknown variable\n"),
+            "{:?}",
+            output,
+        );
+    }
+
+    #[test]
+    fn require_primary() {
+        let mut files = SimpleFiles::new();
+        let id = files.add("test", "let x = y;\n");
+        let diagnostic = Diagnostic::error()
+            .with_message("unknown variable")
+            .with_labels(vec![
+                Label::secondary(id, 8..9).with_message("used here"),
+                Label::secondary(id, 4..5).with_message("assigned here"),
+            ]);
+
+        let emit_with = |require_primary| {
+            let config = Config {
+                display_style: DisplayStyle::Short,
+                require_primary,
+                ..Config::default()
+            };
+            let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+            emit(&mut writer, &config, &files, &diagnostic)
+                .map(|_| String::from_utf8(writer.into_inner()).unwrap())
+        };
+
+        assert_eq!(
+            emit_with(RequirePrimary::PromoteFirst).unwrap(),
+            "test:1:5: error: unknown variable\n",
+        );
+        assert_eq!(
+            emit_with(RequirePrimary::AllowSecondary).unwrap(),
+            "error: unknown variable\n",
+        );
+
+        // Labels that start at the same position are ordered by their end.
+        let diagnostic = Diagnostic::error()
+            .with_message("unknown variable")
+            .with_labels(vec![
+                Label::secondary(id, 4..9).with_message("in this assignment"),
+                Label::secondary(id, 4..5).with_message("assigned here"),
+            ]);
+        let config = Config {
+            require_primary: RequirePrimary::PromoteFirst,
+            ..Config::default()
+        };
+        let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+        emit(&mut writer, &config, &files, &diagnostic).unwrap();
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(output.contains("  │     ^----\n"), "{}", output);
+    }
+
+    #[test]
+    fn byte_offset_base() {
+        let mut files = SimpleFiles::new();
+        let id = files.add("test", "let x = 1;\nlet y = x;\n");
+
+        let emit_with = |range, byte_offset_base| {
+            let diagnostic = Diagnostic::error()
+                .with_message("oh no")
+                .with_labels(vec![Label::primary(id, range)]);
+            let config = Config {
+                byte_offset_base,
+                ..Config::default()
+            };
+            let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+            emit(&mut writer, &config, &files, &diagnostic).unwrap();
+            String::from_utf8(writer.into_inner()).unwrap()
+        };
+
+        assert_eq!(emit_with(20..21, 1), emit_with(19..20, 0));
+        assert_eq!(emit_with(1..2, 1), emit_with(0..1, 0));
+        assert_eq!(emit_with(0..2, 1), emit_with(0..1, 0));
+        assert_eq!(emit_with(0..0, 1), emit_with(0..0, 0));
+    }
+
+    #[test]
+    fn severity_case() {
+        let files = SimpleFiles::<&str, &str>::new();
+        let diagnostic = Diagnostic::error().with_code("E0001").with_message("oh no");
+
+        let emit_with = |severity_case| {
+            let config = Config {
+                severity_case,
+                display_style: DisplayStyle::Short,
+                ..Config::default()
+            };
+            let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+            emit(&mut writer, &config, &files, &diagnostic).unwrap();
+            String::from_utf8(writer.into_inner()).unwrap()
+        };
+
+        assert_eq!(emit_with(SeverityCase::Lower), "error[E0001]: oh no\n");
+        assert_eq!(emit_with(SeverityCase::Upper), "ERROR[E0001]: oh no\n");
+        assert_eq!(emit_with(SeverityCase::Title), "Error[E0001]: oh no\n");
+    }
+
+    /// Emit a diagnostic with [`emit`] and with [`emit_resolved`], resolving its
+    /// labels by looking them up in the file.
+    fn emit_and_emit_resolved(
+        source: &str,
+        labels: &[(LabelStyle, std::ops::Range<usize>)],
+    ) -> (String, String) {
+        let mut files = SimpleFiles::new();
+        let id = files.add("test", source);
+        let diagnostic = Diagnostic::error()
+            .with_message("mismatched types")
+            .with_labels(
+                labels
+                    .iter()
+                    .map(|(style, range)| {
+                        Label::new(*style, id, range.clone()).with_message("label")
+                    })
+                    .collect(),
+            );
+
+        let resolved = Diagnostic::error()
+            .with_message("mismatched types")
+            .with_labels(
+                diagnostic
+                    .labels
+                    .iter()
+                    .map(|label| {
+                        let line_index = files.line_index(id, label.range.start).unwrap();
+                        let line_range = files.line_range(id, line_index).unwrap();
+                        let location = files.location(id, label.range.start).unwrap();
+                        let location = ResolvedLocation {
+                            name: "test",
+                            line_number: location.line_number,
+                            column_number: location.column_number,
+                            line_text: &source[line_range.clone()],
+                        };
+                        let range = (label.range.start - line_range.start)
+                            ..(label.range.end - line_range.start);
+                        Label::new(label.style, location, range).with_message(&label.message)
+                    })
+                    .collect(),
+            );
+
+        let config = Config::default();
+        let mut expected = termcolor::NoColor::new(Vec::<u8>::new());
+        emit(&mut expected, &config, &files, &diagnostic).unwrap();
+        let mut actual = termcolor::NoColor::new(Vec::<u8>::new());
+        emit_resolved(&mut actual, &config, &resolved).unwrap();
+
+        (
+            String::from_utf8(expected.into_inner()).unwrap(),
+            String::from_utf8(actual.into_inner()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn resolved_emit_matches_emit() {
+        // Adjacent lines, and lines separated by a source break.
+        let (expected, actual) = emit_and_emit_resolved(
+            "let x = 1;\nlet y = x + \"a\";\n\n\nlet z = y;\n",
+            &[
+                (LabelStyle::Primary, 23..26),
+                (LabelStyle::Secondary, 4..5),
+                (LabelStyle::Secondary, 38..39),
+            ],
+        );
+        assert_eq!(actual, expected);
+
+        // Lines separated by a single line, which is rendered without its text.
+        let (expected, actual) = emit_and_emit_resolved(
+            "let x = 1;\nlet y = 2;\nlet z = x;\n",
+            &[(LabelStyle::Primary, 30..31), (LabelStyle::Secondary, 4..5)],
+        );
+        assert!(expected.contains("2 │ let y = 2;\n"), "{:?}", expected);
+        assert_eq!(actual, expected.replace("2 │ let y = 2;\n", "2 │ \n"));
+
+        // Several labels on a single line, without a trailing newline.
+        let (expected, actual) = emit_and_emit_resolved(
+            "let x = y + 1;",
+            &[(LabelStyle::Primary, 8..9), (LabelStyle::Secondary, 4..5)],
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn render_by_severity() {
+        let mut files = SimpleFiles::new();
+        let id = files.add("test", "let x = y;\n");
+        let diagnostics = vec![
+            Diagnostic::warning().with_message("unused variable"),
+            Diagnostic::error()
+                .with_message("unknown variable")
+                .with_labels(vec![Label::primary(id, 8..9)]),
+            Diagnostic::warning().with_message("unused import"),
+        ];
+
+        let config = Config {
+            display_style: DisplayStyle::Short,
+            ..Config::default()
+        };
+        let output = super::render_by_severity(&config, &files, &diagnostics).unwrap();
+
+        assert_eq!(
+            output.into_iter().collect::<Vec<_>>(),
+            vec![
+                (
+                    Severity::Warning,
+                    String::from("warning: unused variable\nwarning: unused import\n")
+                ),
+                (
+                    Severity::Error,
+                    String::from("test:1:9: error: unknown variable\n")
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn emit_to_sink() {
+        use termcolor::ColorSpec;
+
+        #[derive(Debug, PartialEq)]
+        enum Call {
+            Text(String),
+            Style(ColorSpec),
+            Reset,
+        }
+
+        struct RecordingSink(Vec<Call>);
+
+        impl StyledSink for RecordingSink {
+            fn write_str(&mut self, text: &str) -> std::io::Result<()> {
+                match self.0.last_mut() {
+                    Some(Call::Text(previous)) => previous.push_str(text),
+                    _ => self.0.push(Call::Text(text.to_owned())),
+                }
+                Ok(())
+            }
+
+            fn set_style(&mut self, spec: &ColorSpec) -> std::io::Result<()> {
+                self.0.push(Call::Style(spec.clone()));
+                Ok(())
+            }
+
+            fn reset(&mut self) -> std::io::Result<()> {
+                self.0.push(Call::Reset);
+                Ok(())
+            }
+        }
+
+        let files = SimpleFiles::<&str, &str>::new();
+        let diagnostic = Diagnostic::error().with_message("oh no");
+        let config = Config {
+            display_style: DisplayStyle::Short,
+            ..Config::default()
+        };
+        let mut sink = RecordingSink(Vec::new());
+        let summary = super::emit_to_sink(&mut sink, &config, &files, &diagnostic).unwrap();
+
+        assert_eq!(
+            sink.0,
+            vec![
+                Call::Style(config.styles.header(Severity::Error).clone()),
+                Call::Text(String::from("error")),
+                Call::Style(config.styles.header_message.clone()),
+                Call::Text(String::from(": oh no")),
+                Call::Reset,
+                Call::Text(String::from("\n")),
+            ],
+        );
+        assert_eq!(summary.bytes_written, "error: oh no\n".len());
+    }
+
+    #[test]
+    fn report() {
+        struct ParseError;
+
+        impl From<ParseError> for Diagnostic<()> {
+            fn from(_: ParseError) -> Diagnostic<()> {
+                Diagnostic::error().with_message("expected an expression")
+            }
+        }
+
+        let file = SimpleFile::new("test", "");
+        let config = Config::default();
+        let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+
+        let value = super::report(&mut writer, &config, &file, Ok::<_, ParseError>(1)).unwrap();
+        assert_eq!(value, Some(1));
+        assert!(writer.get_ref().is_empty());
+
+        let value = super::report(&mut writer, &config, &file, Err::<u32, _>(ParseError)).unwrap();
+        assert_eq!(value, None);
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "error: expected an expression\n\n",
+        );
+    }
+
+    #[test]
+    fn min_width_fallback() {
+        let source = format!("{}let x = 1 +;\n", "\n".repeat(123_455));
+        let file = SimpleFile::new("test", source.as_str());
+        let start = source.len() - 3;
+        let diagnostic = Diagnostic::error()
+            .with_message("expected an expression")
+            .with_labels(vec![Label::primary((), start..start + 1)]);
+
+        let emit_with = |terminal_width, min_width_fallback| {
+            let config = Config {
+                terminal_width: Some(terminal_width),
+                min_width_fallback,
+                ..Config::default()
+            };
+            let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+            emit(&mut writer, &config, &file, &diagnostic).unwrap();
+            String::from_utf8(writer.into_inner()).unwrap()
+        };
+
+        assert_eq!(
+            emit_with(5, true),
+            "test:123456:11: error: expected an expression\n",
+        );
+        assert!(emit_with(5, false).contains("123456 │ let x = 1 +;\n"));
+        assert!(emit_with(80, true).contains("123456 │ let x = 1 +;\n"));
+    }
+
+    #[test]
+    fn emit_all_with() {
+        let diagnostics = vec![
+            Diagnostic::error()
+                .with_message("oh no")
+                .with_notes(vec![String::from("this is why")]),
+            Diagnostic::warning()
+                .with_message("careful")
+                .with_notes(vec![String::from("this is how")]),
+        ];
+        let files = SimpleFiles::<&str, &str>::new();
+        let options = |diagnostic: &Diagnostic<usize>| RenderOptions {
+            show_notes: diagnostic.severity != Severity::Error,
+            ..RenderOptions::default()
+        };
+
+        let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+        super::emit_all_with(
+            &mut writer,
+            &Config::default(),
+            &files,
+            &diagnostics,
+            options,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            concat!(
+                "error: oh no\n",
+                "\n",
+                "warning: careful\n",
+                " = this is how\n",
+                "\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn emit_table() {
+        let mut files = SimpleFiles::new();
+        let id = files.add("main.rs", "let x = 1;\nlet y: u32 = \"two\";\n");
+        let diagnostics = vec![
+            Diagnostic::error()
+                .with_code("E0308")
+                .with_message("mismatched types")
+                .with_labels(vec![Label::primary(id, 24..29)]),
+            Diagnostic::warning()
+                .with_message("unused variable: `x`, consider prefixing it with an underscore")
+                .with_labels(vec![Label::primary(id, 4..5)]),
+            Diagnostic::note().with_message("2 diagnostics\nin total"),
+        ];
+        let config = Config {
+            terminal_width: Some(60),
+            ..Config::default()
+        };
+
+        let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+        super::emit_table(&mut writer, &config, &files, &diagnostics).unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            concat!(
+                "severity  code   location      message\n",
+                "error     E0308  main.rs:2:14  mismatched types\n",
+                "warning          main.rs:1:5   unused variable: `x`, consid…\n",
+                "note                           2 diagnostics in total\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn emit_ansi_bytes() {
+        use termcolor::Color;
+
+        let file = SimpleFile::new("test", "let x = 1 +;\n");
+        let diagnostic = Diagnostic::error()
+            .with_code("E0001")
+            .with_message("oh no")
+            .with_labels(vec![Label::primary((), 10..11).with_message("here")]);
+        let config = Config {
+            styles: Styles::with_blue(Color::Blue),
+            ..Config::default()
+        };
+
+        let mut writer = termcolor::Buffer::ansi();
+        emit(&mut writer, &config, &file, &diagnostic).unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            concat!(
+                "\u{1b}[0m\u{1b}[1m\u{1b}[38;5;9merror[E0001]",
+                "\u{1b}[0m\u{1b}[1m: oh no\u{1b}[0m\n",
+                "  \u{1b}[0m\u{1b}[34m┌─\u{1b}[0m test:1:11\n",
+                "  \u{1b}[0m\u{1b}[34m│\u{1b}[0m\n",
+                "\u{1b}[0m\u{1b}[34m1\u{1b}[0m \u{1b}[0m\u{1b}[34m│\u{1b}[0m ",
+                "let x = 1 \u{1b}[0m\u{1b}[31m+\u{1b}[0m;\n",
+                "  \u{1b}[0m\u{1b}[34m│\u{1b}[0m           ",
+                "\u{1b}[0m\u{1b}[31m^\u{1b}[0m \u{1b}[0m\u{1b}[31mhere\u{1b}[0m\n",
+                "\n",
+            ),
+        );
+
+        // Focusing a label dims everything else, both the styled and the
+        // uncolored text.
+        let file = SimpleFile::new("test", "let x = 1;\nx +;\n");
+        let diagnostic = Diagnostic::error()
+            .with_message("oh no")
+            .with_labels(vec![Label::primary((), 13..14), Label::secondary((), 4..5)]);
+        let config = Config {
+            styles: Styles::with_blue(Color::Blue),
+            focus_label: Some(0),
+            ..Config::default()
+        };
+
+        let mut writer = termcolor::Buffer::ansi();
+        emit(&mut writer, &config, &file, &diagnostic).unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            concat!(
+                "\u{1b}[0m\u{1b}[2m\u{1b}[0m\u{1b}[1m\u{1b}[2m\u{1b}[38;5;9merror",
+                "\u{1b}[0m\u{1b}[1m\u{1b}[2m: oh no\u{1b}[0m\u{1b}[2m\n",
+                "  \u{1b}[0m\u{1b}[2m\u{1b}[34m┌─\u{1b}[0m\u{1b}[2m test:2:3\n",
+                "  \u{1b}[0m\u{1b}[2m\u{1b}[34m│\u{1b}[0m\u{1b}[2m\n",
+                "\u{1b}[0m\u{1b}[2m\u{1b}[34m1\u{1b}[0m\u{1b}[2m \u{1b}[0m\u{1b}[2m\u{1b}[34m│\u{1b}[0m\u{1b}[2m ",
+                "let x = 1;\n",
+                "  \u{1b}[0m\u{1b}[2m\u{1b}[34m│\u{1b}[0m\u{1b}[2m     ",
+                "\u{1b}[0m\u{1b}[2m\u{1b}[34m-\u{1b}[0m\u{1b}[2m\n",
+                "\u{1b}[0m\u{1b}[0m\u{1b}[34m2\u{1b}[0m \u{1b}[0m\u{1b}[34m│\u{1b}[0m ",
+                "x \u{1b}[0m\u{1b}[31m+\u{1b}[0m;\n",
+                "  \u{1b}[0m\u{1b}[34m│\u{1b}[0m   \u{1b}[0m\u{1b}[31m^\u{1b}[0m\n",
+                "\u{1b}[0m\u{1b}[2m\u{1b}[0m\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn emit_summary_line() {
+        use std::io::Write;
+
+        let files = SimpleFiles::<&str, &str>::new();
+        let config = Config::default();
+        let mut writer = termcolor::NoColor::new(Vec::<u8>::new());
+
+        let mut summary = EmitSummary::default();
+        let batch = vec![Diagnostic::warning(), Diagnostic::note()];
+        summary += emit_all(&mut writer, &config, &files, &batch).unwrap();
+        summary += emit_all(&mut writer, &config, &files, &[Diagnostic::warning()]).unwrap();
+        assert_eq!((summary.errors, summary.warnings, summary.notes), (0, 2, 1));
+
+        let mut writer = termcolor::Ansi::new(Vec::<u8>::new());
+        let line_summary = super::emit_summary(&mut writer, &config, &summary).unwrap();
+        assert_eq!(
+            line_summary.bytes_written,
+            "0 errors, 2 warnings, 1 note\n".len()
+        );
+        assert_eq!(line_summary.count(Severity::Warning), 0);
+
+        let mut expected = termcolor::Ansi::new(Vec::<u8>::new());
+        write!(expected, "0 errors, ").unwrap();
+        expected
+            .set_color(config.styles.header(Severity::Warning))
+            .unwrap();
+        write!(expected, "2 warnings").unwrap();
+        expected.reset().unwrap();
+        write!(expected, ", ").unwrap();
+        expected
+            .set_color(config.styles.header(Severity::Note))
+            .unwrap();
+        write!(expected, "1 note").unwrap();
+        expected.reset().unwrap();
+        writeln!(expected).unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            String::from_utf8(expected.into_inner()).unwrap(),
+        );
+    }
 }
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use termcolor::{Color, ColorSpec};
 
 use crate::diagnostic::{LabelStyle, Severity};
@@ -12,6 +13,11 @@ pub struct Config {
     pub display_style: DisplayStyle,
     /// Column width of tabs.
     /// Defaults to: `4`.
+    ///
+    /// Tabs are expanded to the next tab stop when rendering source code. A
+    /// label that covers part of the expansion of a tab is rendered as covering
+    /// the whole tab: the start of the label is rounded down to the first column
+    /// of the tab, and the end of the label is rounded up to its last column.
     pub tab_width: usize,
     /// Styles to use when rendering the diagnostic.
     pub styles: Styles,
@@ -25,6 +31,16 @@ pub struct Config {
     pub start_context_lines: usize,
     /// The minimum number of lines to be shown before the line on which a multiline [`Label`] ends.
     ///
+    /// Lines of a multiline label that are not shown are collapsed into a
+    /// single source break, and the vertical connectors of the multiline
+    /// labels that span them are continued through the break.
+    ///
+    /// ```text
+    /// 4 │ │     let x3 = 3;
+    ///   · │
+    /// 7 │ │     let x6 = 6;
+    /// ```
+    ///
     /// Defaults to: `1`.
     ///
     /// [`Label`]: crate::diagnostic::Label
@@ -37,6 +53,349 @@ pub struct Config {
     ///
     /// Defaults to: `0`.
     pub after_label_lines: usize,
+    /// The number of empty lines to render above and below the source lines of
+    /// each snippet, within the left border.
+    ///
+    /// Defaults to: `0`.
+    pub code_frame_padding: usize,
+    /// The width of the terminal in columns, if known.
+    ///
+    /// This is used by:
+    ///
+    /// - [`DisplayStyle::Narrow`] and [`DisplayStyle::Elm`], to wrap messages
+    ///   and notes, and as the cap for [`Config::per_file_width`].
+    /// - [`Config::min_width_fallback`], to decide whether a diagnostic is
+    ///   rendered in the short format instead.
+    /// - [`emit_table`], to truncate messages that do not fit on their row.
+    /// - [`emit_side_by_side`], to split the output into two columns.
+    ///
+    /// Other display styles do not wrap their output.
+    ///
+    /// Defaults to: `None`.
+    ///
+    /// [`DisplayStyle::Narrow`]: DisplayStyle::Narrow
+    /// [`DisplayStyle::Elm`]: DisplayStyle::Elm
+    /// [`emit_table`]: crate::term::emit_table
+    /// [`emit_side_by_side`]: crate::term::emit_side_by_side
+    pub terminal_width: Option<usize>,
+    /// The byte offset that label ranges are relative to.
+    ///
+    /// This is useful when byte offsets come from a tool that starts counting
+    /// at `1` rather than at `0`. Label ranges are converted to zero-based
+    /// offsets by subtracting this value before rendering. Offsets below this
+    /// value are clamped to `0`, so they point at the start of the file, in
+    /// the same way that [`Diagnostic::clamp_ranges`] clamps offsets past the
+    /// end of the file.
+    ///
+    /// Defaults to: `0`.
+    ///
+    /// [`Diagnostic::clamp_ranges`]: crate::diagnostic::Diagnostic::clamp_ranges
+    pub byte_offset_base: usize,
+    /// The maximum number of bytes of a source line to render.
+    ///
+    /// Lines longer than this are clipped, and are rendered with a
+    /// [`Chars::source_clip`] marker in place of the rest of the line. This
+    /// avoids scanning the whole of a pathologically long line, such as a
+    /// minified file, in order to render a single label. This applies to the
+    /// source lines of every display style, and labels past the clipped extent
+    /// are rendered at the marker, including either end of multi-line labels.
+    ///
+    /// Defaults to: `None`.
+    pub max_line_scan: Option<usize>,
+    /// Whether to remove the leading indentation that all of the rendered lines
+    /// of a snippet have in common.
+    ///
+    /// This saves horizontal space when rendering deeply nested code. Lines that
+    /// only contain whitespace are not taken into account.
+    ///
+    /// Defaults to: `false`.
+    pub dedent_common_indent: bool,
+    /// Whether to skip notes that are empty or only contain whitespace.
+    ///
+    /// Defaults to: `true`.
+    pub skip_empty_notes: bool,
+    /// The letter case to use when rendering the severity in the header of a
+    /// diagnostic.
+    ///
+    /// Defaults to: [`SeverityCase::Lower`].
+    ///
+    /// [`SeverityCase::Lower`]: SeverityCase::Lower
+    pub severity_case: SeverityCase,
+    /// Whether to render secondary labels in files without primary labels as
+    /// references underneath the snippet of the primary labels, rather than
+    /// as snippets of their own.
+    ///
+    /// ```text
+    ///   ┌─ main.rs:3:5
+    ///   │
+    /// 3 │     foo(1, 2);
+    ///   │     ^^^ expected 1 argument
+    ///   │ defined here (see lib.rs:1:8)
+    /// ```
+    ///
+    /// Defaults to: `false`.
+    pub inline_cross_file_refs: bool,
+    /// The name to show in locations of files whose name is empty, such as
+    /// sources that were not read from a file.
+    ///
+    /// ```text
+    ///   ┌─ <input>:3:5
+    /// ```
+    ///
+    /// Defaults to: `"<input>"`.
+    pub anonymous_file_name: String,
+    /// The certainty, as a percentage, below which labels are rendered as
+    /// uncertain, with [`Chars::single_uncertain_caret`] and a `(maybe)`
+    /// qualifier after their message. Labels without a certainty are always
+    /// rendered as certain.
+    ///
+    /// ```text
+    /// 3 │     let y = x / 0;
+    ///   │             ┄┄┄┄┄ division by zero (maybe)
+    /// ```
+    ///
+    /// Defaults to: `50`.
+    pub certainty_threshold: u8,
+    /// Whether to end the output of each diagnostic with a newline. If this is
+    /// `false`, the output ends at its last character that is not a newline,
+    /// which is useful when the caller separates diagnostics itself.
+    ///
+    /// Defaults to: `true`.
+    pub trailing_newline: bool,
+    /// Whether [`emit_all`] should abbreviate notes that were already emitted
+    /// earlier in the same batch, rather than repeating them in full.
+    ///
+    /// ```text
+    /// = (same as above)
+    /// ```
+    ///
+    /// Defaults to: `false`.
+    ///
+    /// [`emit_all`]: crate::term::emit_all
+    pub dedup_repeated_notes: bool,
+    /// Whether to write tab characters in the source code as they are, rather
+    /// than expanding them to spaces, for tools that parse the output. Carets
+    /// are still aligned as if the tabs were expanded to [`Config::tab_width`]
+    /// columns. This does not apply to [`term::emit_side_by_side`], which needs
+    /// to expand tabs to clip lines to their columns.
+    ///
+    /// Defaults to: `false`.
+    ///
+    /// [`term::emit_side_by_side`]: crate::term::emit_side_by_side
+    pub preserve_tabs_in_output: bool,
+    /// URLs of pages with more information about diagnostics of a severity,
+    /// which are appended to each diagnostic of that severity as a note.
+    ///
+    /// ```text
+    /// = help: see https://example.com/errors
+    /// ```
+    ///
+    /// Defaults to: no URLs.
+    pub severity_help_urls: BTreeMap<Severity, String>,
+    /// The number of spaces between the line numbers and the left border of
+    /// source snippets.
+    ///
+    /// ```text
+    /// 2 │ (+ test "")
+    ///  ^
+    /// ```
+    ///
+    /// Defaults to: `1`.
+    pub gutter_padding_left: usize,
+    /// The number of spaces between the left border (and the lines of any
+    /// multi-line labels) and the source code of source snippets.
+    ///
+    /// ```text
+    /// 2 │ (+ test "")
+    ///    ^
+    /// ```
+    ///
+    /// Defaults to: `1`.
+    pub gutter_padding_right: usize,
+    /// How to align line numbers within the gutter, when they are shorter
+    /// than the longest line number of the snippet.
+    ///
+    /// Defaults to: [`GutterAlign::Right`].
+    ///
+    /// [`GutterAlign::Right`]: GutterAlign::Right
+    pub gutter_align: GutterAlign,
+    /// Whether to render labels with the same style, file, range, and message
+    /// as a single label, with the number of labels after its message.
+    ///
+    /// ```text
+    /// 2 │ (+ test "")
+    ///   │         ^^ expected `Int` but found `String` (×3)
+    /// ```
+    ///
+    /// Defaults to: `false`.
+    pub coalesce_identical_labels: bool,
+    /// Whether to render labels with the same style, file, and message whose
+    /// ranges touch or overlap as a single label with one continuous underline.
+    ///
+    /// ```text
+    /// 2 │ let x = @#$;
+    ///   │         ^^^ unexpected characters
+    /// ```
+    ///
+    /// Defaults to: `false`.
+    pub merge_adjacent_labels: bool,
+    /// What to do with diagnostics that have labels, but no primary labels.
+    ///
+    /// Defaults to: [`RequirePrimary::AllowSecondary`].
+    ///
+    /// [`RequirePrimary::AllowSecondary`]: RequirePrimary::AllowSecondary
+    pub require_primary: RequirePrimary,
+    /// Whether to prefix the header of each diagnostic emitted by
+    /// [`term::emit_all`] with its index in the batch.
+    ///
+    /// ```text
+    /// [2/3] error[E0308]: mismatched types
+    /// ```
+    ///
+    /// Defaults to: `false`.
+    ///
+    /// [`term::emit_all`]: crate::term::emit_all
+    pub show_index: bool,
+    /// The number of characters around each single-line label in which spaces
+    /// and tabs are revealed, by rendering them as [`Chars::whitespace_space`]
+    /// and [`Chars::whitespace_tab`]. This helps with labels that point at
+    /// whitespace, such as trailing whitespace, without making the rest of the
+    /// line harder to read.
+    ///
+    /// ```text
+    /// 1 │ let x = 1;···
+    ///   │           ^^^ trailing whitespace
+    /// ```
+    ///
+    /// Defaults to: `None`, which does not reveal any whitespace.
+    pub reveal_whitespace_margin: Option<usize>,
+    /// Icons to write before the severity in the header of diagnostics, such
+    /// as for terminals that support emoji. Severities without an icon are
+    /// written without one.
+    ///
+    /// ```text
+    /// ❌ error[E0308]: mismatched types
+    /// ```
+    ///
+    /// Defaults to: `None`.
+    pub severity_icons: Option<BTreeMap<Severity, String>>,
+    /// The text to use as the message of diagnostics with an empty message.
+    /// If such a diagnostic has notes, its first note is used as the message
+    /// instead.
+    ///
+    /// ```text
+    /// error: <no message>
+    /// ```
+    ///
+    /// Defaults to: `None`, which renders empty messages as they are.
+    pub empty_message_text: Option<String>,
+    /// Whether to group the digits of line numbers in threes, separated by
+    /// [`Chars::digit_group_separator`], to make large line numbers easier
+    /// to read.
+    ///
+    /// ```text
+    /// 123_456 │ let x = 1;
+    ///         │     ^
+    /// ```
+    ///
+    /// Defaults to: `false`.
+    pub group_line_number_digits: bool,
+    /// Whether to fold the secondary labels of diagnostics with a primary
+    /// label into a summary of their locations, which is rendered as the
+    /// first note of the diagnostic. This is useful for focusing on the
+    /// primary labels.
+    ///
+    /// ```text
+    ///   = … and 2 related locations: main.rs:1:5, lib.rs:3:1
+    /// ```
+    ///
+    /// Defaults to: `false`.
+    pub fold_secondary_labels: bool,
+    /// Whether [`DisplayStyle::Narrow`] should wrap the messages of the labels
+    /// in each file to the width of the widest labelled line in that file,
+    /// rather than to [`Config::terminal_width`]. The width is still capped at
+    /// the terminal width, and is at least 40 columns if the terminal is as
+    /// wide, so that messages stay readable next to very short lines.
+    ///
+    /// Defaults to: `false`.
+    ///
+    /// [`DisplayStyle::Narrow`]: DisplayStyle::Narrow
+    pub per_file_width: bool,
+    /// Whether to render the code of a diagnostic as a badge on its own line
+    /// above the header, instead of after the severity. Nothing extra is
+    /// rendered for diagnostics without a code.
+    ///
+    /// ```text
+    /// ┌─ E0412 ─┐
+    /// error: cannot find type `Foo` in this scope
+    /// ```
+    ///
+    /// Defaults to: `false`.
+    pub code_badge_line: bool,
+    /// Whether to annotate the carets of primary labels that point at a single
+    /// character with the column number of that character. Labels spanning
+    /// more than one character are not annotated.
+    ///
+    /// ```text
+    /// 2 │ (+ test "")
+    ///   │    ^ (col 4) expected a number
+    /// ```
+    ///
+    /// Defaults to: `false`.
+    pub annotate_caret_column: bool,
+    /// The index of a label to focus on, by rendering everything except for
+    /// the source lines of that label dimmed. This is only used by
+    /// [`DisplayStyle::Rich`].
+    ///
+    /// Defaults to: `None`.
+    ///
+    /// [`DisplayStyle::Rich`]: DisplayStyle::Rich
+    pub focus_label: Option<usize>,
+    /// Whether to render diagnostics in the format of [`DisplayStyle::Short`]
+    /// if [`Config::terminal_width`] is too narrow to fit the gutter of the
+    /// source lines and a few columns of source code.
+    ///
+    /// Defaults to: `false`.
+    ///
+    /// [`DisplayStyle::Short`]: DisplayStyle::Short
+    pub min_width_fallback: bool,
+    /// Whether to render the messages of single-line labels above their source
+    /// line as captions, connected to the start of the label, rather than
+    /// underneath the carets.
+    ///
+    /// ```text
+    ///   │         expected `Int` but found `String`
+    ///   │         │
+    /// 2 │ (+ test "")
+    ///   │         ^^
+    /// ```
+    ///
+    /// Defaults to: `false`.
+    pub message_above_underline: bool,
+    /// The maximum number of separate blocks of source code to render for
+    /// each diagnostic, if any. Labels whose lines are adjacent or separated
+    /// by a single line form a block together, and blocks with primary labels
+    /// are kept before the other blocks. The labels of the remaining blocks are
+    /// not rendered, and a note with the number of omitted blocks is added to
+    /// the diagnostic instead.
+    ///
+    /// ```text
+    ///   = … 4 more locations not shown
+    /// ```
+    ///
+    /// Defaults to: `None`.
+    pub max_snippet_blocks: Option<usize>,
+    /// The maximum width of the code in the header of a diagnostic, if any.
+    /// Longer codes are truncated with the [`Chars::source_clip`] marker. This
+    /// only affects the header: the full code is still used by machine-readable
+    /// output like `emit_jsonl`.
+    ///
+    /// ```text
+    /// error[security/in…]: possible SQL injection
+    /// ```
+    ///
+    /// Defaults to: `None`.
+    pub max_code_width: Option<usize>,
 }
 
 impl Default for Config {
@@ -50,6 +409,77 @@ impl Default for Config {
             end_context_lines: 1,
             before_label_lines: 0,
             after_label_lines: 0,
+            code_frame_padding: 0,
+            terminal_width: None,
+            byte_offset_base: 0,
+            max_line_scan: None,
+            dedent_common_indent: false,
+            skip_empty_notes: true,
+            severity_case: SeverityCase::Lower,
+            inline_cross_file_refs: false,
+            anonymous_file_name: String::from("<input>"),
+            certainty_threshold: 50,
+            trailing_newline: true,
+            dedup_repeated_notes: false,
+            preserve_tabs_in_output: false,
+            severity_help_urls: BTreeMap::new(),
+            gutter_padding_left: 1,
+            gutter_padding_right: 1,
+            gutter_align: GutterAlign::Right,
+            coalesce_identical_labels: false,
+            merge_adjacent_labels: false,
+            require_primary: RequirePrimary::AllowSecondary,
+            show_index: false,
+            reveal_whitespace_margin: None,
+            severity_icons: None,
+            empty_message_text: None,
+            group_line_number_digits: false,
+            fold_secondary_labels: false,
+            per_file_width: false,
+            code_badge_line: false,
+            annotate_caret_column: false,
+            focus_label: None,
+            min_width_fallback: false,
+            message_above_underline: false,
+            max_snippet_blocks: None,
+            max_code_width: None,
+        }
+    }
+}
+
+impl Config {
+    /// A config that keeps the output as short as possible, such as for a
+    /// `--quiet` flag.
+    ///
+    /// This uses [`DisplayStyle::Compact`], shows as few lines of multi-line
+    /// labels as possible, removes the common indentation of the shown lines,
+    /// and shows labels in other files as inline references.
+    pub fn compact() -> Config {
+        Config {
+            display_style: DisplayStyle::Compact,
+            start_context_lines: 0,
+            end_context_lines: 0,
+            before_label_lines: 0,
+            after_label_lines: 0,
+            dedent_common_indent: true,
+            inline_cross_file_refs: true,
+            ..Config::default()
+        }
+    }
+
+    /// A config that shows as much context as possible, such as for a
+    /// `--verbose` flag.
+    ///
+    /// This uses [`DisplayStyle::Rich`], shows more lines of multi-line labels,
+    /// and shows two lines of context before and after each label.
+    pub fn verbose() -> Config {
+        Config {
+            display_style: DisplayStyle::Rich,
+            start_context_lines: 5,
+            end_context_lines: 3,
+            before_label_lines: 2,
+            after_label_lines: 2,
+            ..Config::default()
         }
     }
 }
@@ -73,6 +503,38 @@ pub enum DisplayStyle {
     ///
     /// ```
     Rich,
+    /// Output a compact diagnostic for diagnostics with a single, single-line
+    /// label and no notes, with the message of the label inline after the
+    /// underline and without any empty lines.
+    ///
+    /// ```text
+    /// test:2:9: error[E0001]: unexpected type in `+` application
+    /// 2 │ (+ test "")
+    ///   │         ^^ expected `Int` but found `String`
+    /// ```
+    ///
+    /// Diagnostics of any other shape are rendered using [`DisplayStyle::Rich`].
+    ///
+    /// [`DisplayStyle::Rich`]: DisplayStyle::Rich
+    Compact,
+    /// Output a diagnostic for narrow terminals, with each label shown on its
+    /// own line of source code, preceded by its locus. Messages are always
+    /// shown below the carets, and messages and notes are wrapped to
+    /// [`Config::terminal_width`], or `40` columns if it is not set. Leading
+    /// indentation is removed from source lines.
+    ///
+    /// ```text
+    /// error[E0001]: unexpected type in `+` application
+    /// ┌─ test:2:9
+    /// 2│ (+ test "")
+    ///  │         ^^
+    ///  │ expected `Int` but found
+    ///  │ `String`
+    /// = expected type `Int`
+    ///      found type `String`
+    ///
+    /// ```
+    Narrow,
     /// Output a condensed diagnostic, with a line number, severity, message and notes (if any).
     ///
     /// ```text
@@ -90,9 +552,104 @@ pub enum DisplayStyle {
     /// error[E0002]: Bad config found
     /// ```
     Short,
+    /// Output a spacious diagnostic in the style of the Elm compiler, with
+    /// each label's source line indented and its message written below as a
+    /// paragraph. Messages and notes are wrapped to [`Config::terminal_width`],
+    /// or `80` columns if it is not set.
+    ///
+    /// ```text
+    /// error[E0001]: unexpected type in `+` application
+    ///
+    /// test:2:9
+    ///
+    ///     2│ (+ test "")
+    ///      │         ^^
+    ///
+    /// expected `Int` but found `String`
+    ///
+    /// expected type `Int`
+    ///      found type `String`
+    ///
+    /// ```
+    Elm,
+}
+
+/// What to do with diagnostics that have labels, but no primary labels.
+///
+/// To reject these diagnostics instead, check
+/// [`Diagnostic::is_missing_primary_label`] before emitting them.
+///
+/// [`Diagnostic::is_missing_primary_label`]: crate::diagnostic::Diagnostic::is_missing_primary_label
+#[derive(Clone, Debug)]
+pub enum RequirePrimary {
+    /// Render the secondary label that starts first as a primary label.
+    PromoteFirst,
+    /// Render the diagnostic with only secondary labels.
+    AllowSecondary,
+}
+
+/// The letter case to use when rendering the severity of a diagnostic.
+#[derive(Clone, Debug)]
+pub enum SeverityCase {
+    /// Render the severity in lowercase.
+    ///
+    /// ```text
+    /// error[E0001]: unexpected type in `+` application
+    /// ```
+    Lower,
+    /// Render the severity in uppercase.
+    ///
+    /// ```text
+    /// ERROR[E0001]: unexpected type in `+` application
+    /// ```
+    Upper,
+    /// Render the severity with its first letter in uppercase.
+    ///
+    /// ```text
+    /// Error[E0001]: unexpected type in `+` application
+    /// ```
+    Title,
+}
+
+/// How to align line numbers within the gutter of source snippets.
+#[derive(Clone, Debug)]
+pub enum GutterAlign {
+    /// Align line numbers to the left of the gutter.
+    ///
+    /// ```text
+    ///    │
+    /// 9  │ let x = 1;
+    /// 10 │ let y = x;
+    ///    │         ^
+    /// ```
+    Left,
+    /// Align line numbers to the right of the gutter.
+    ///
+    /// ```text
+    ///    │
+    ///  9 │ let x = 1;
+    /// 10 │ let y = x;
+    ///    │         ^
+    /// ```
+    Right,
 }
 
 /// Styles to use when rendering the diagnostic.
+///
+/// The renderer only ever changes colors by setting one of these styles or by
+/// resetting the style, so the escape sequences written to an ANSI writer such
+/// as [`termcolor::Buffer::ansi`] depend only on these styles and not on the
+/// platform or terminal. Note that the default styles use cyan instead of blue
+/// on Windows.
+///
+/// The one exception is [`Config::focus_label`], which renders the output
+/// outside of the focused lines dimmed: there, each of these styles is set
+/// with [`ColorSpec::set_dimmed`] added, and resetting the style sets a style
+/// that is only dimmed, so that the uncolored text stays dimmed as well.
+///
+/// [`termcolor::Buffer::ansi`]: termcolor::Buffer::ansi
+/// [`Config::focus_label`]: Config::focus_label
+/// [`ColorSpec::set_dimmed`]: termcolor::ColorSpec::set_dimmed
 #[derive(Clone, Debug)]
 pub struct Styles {
     /// The style to use when rendering bug headers.
@@ -232,6 +789,9 @@ pub struct Chars {
     /// The character to use for marking a single-line secondary label.
     /// Defaults to: `'-'`.
     pub single_secondary_caret: char,
+    /// The character to use for marking a single-line label that is uncertain.
+    /// Defaults to: `'┄'` or `'.'` with [`Chars::ascii()`].
+    pub single_uncertain_caret: char,
 
     /// The character to use for marking the start of a multi-line primary label.
     /// Defaults to: `'^'`.
@@ -264,6 +824,52 @@ pub struct Chars {
     /// The character to use for the left of a pointer underneath a caret.
     /// Defaults to: `'│'` or `'|'` with [`Chars::ascii()`].
     pub pointer_left: char,
+
+    /// The characters to write in front of the message of a primary label.
+    /// Defaults to: `""`.
+    pub primary_message_bullet: String,
+    /// The characters to write in front of the message of a secondary label.
+    /// Defaults to: `""`.
+    pub secondary_message_bullet: String,
+
+    /// The characters to use for marking a source line that was clipped by
+    /// [`Config::max_line_scan`].
+    /// Defaults to: `"…"` or `"..."` with [`Chars::ascii()`].
+    pub source_clip: String,
+    /// The character to draw in place of a line terminator that is covered
+    /// by a single-line label.
+    /// Defaults to: `'⏎'` or `'$'` with [`Chars::ascii()`].
+    pub newline_marker: char,
+    /// The character to put before the number of labels that were coalesced by
+    /// [`Config::coalesce_identical_labels`].
+    /// Defaults to: `'×'` or `'x'` with [`Chars::ascii()`].
+    pub label_count_marker: char,
+    /// The character to render spaces with when they are revealed by
+    /// [`Config::reveal_whitespace_margin`].
+    /// Defaults to: `'·'` or `'.'` with [`Chars::ascii()`].
+    pub whitespace_space: char,
+    /// The character to render the first column of tabs with when they are
+    /// revealed by [`Config::reveal_whitespace_margin`].
+    /// Defaults to: `'→'` or `'>'` with [`Chars::ascii()`].
+    pub whitespace_tab: char,
+    /// The character to draw in place of the line number of a
+    /// [`Label::synthetic_line`].
+    /// Defaults to: `'~'`.
+    ///
+    /// [`Label::synthetic_line`]: crate::diagnostic::Label::synthetic_line
+    pub synthetic_line_marker: char,
+    /// The character to separate groups of digits in line numbers with, when
+    /// [`Config::group_line_number_digits`] is enabled.
+    /// Defaults to: `'_'`.
+    pub digit_group_separator: char,
+    /// The characters to draw before the code of a diagnostic, when
+    /// [`Config::code_badge_line`] is enabled.
+    /// Defaults to: `"┌─"` or `"+-"` with [`Chars::ascii()`].
+    pub code_badge_left: String,
+    /// The characters to draw after the code of a diagnostic, when
+    /// [`Config::code_badge_line`] is enabled.
+    /// Defaults to: `"─┐"` or `"-+"` with [`Chars::ascii()`].
+    pub code_badge_right: String,
 }
 
 impl Default for Chars {
@@ -284,6 +890,7 @@ impl Chars {
 
             single_primary_caret: '^',
             single_secondary_caret: '-',
+            single_uncertain_caret: '┄',
 
             multi_primary_caret_start: '^',
             multi_primary_caret_end: '^',
@@ -296,6 +903,19 @@ impl Chars {
             multi_left: '│',
 
             pointer_left: '│',
+
+            primary_message_bullet: String::new(),
+            secondary_message_bullet: String::new(),
+
+            source_clip: "…".into(),
+            newline_marker: '⏎',
+            label_count_marker: '×',
+            whitespace_space: '·',
+            whitespace_tab: '→',
+            synthetic_line_marker: '~',
+            digit_group_separator: '_',
+            code_badge_left: "┌─".into(),
+            code_badge_right: "─┐".into(),
         }
     }
 
@@ -314,6 +934,7 @@ impl Chars {
 
             single_primary_caret: '^',
             single_secondary_caret: '-',
+            single_uncertain_caret: '.',
 
             multi_primary_caret_start: '^',
             multi_primary_caret_end: '^',
@@ -326,6 +947,63 @@ impl Chars {
             multi_left: '|',
 
             pointer_left: '|',
+
+            primary_message_bullet: String::new(),
+            secondary_message_bullet: String::new(),
+
+            source_clip: "...".into(),
+            newline_marker: '$',
+            label_count_marker: 'x',
+            whitespace_space: '.',
+            whitespace_tab: '>',
+            synthetic_line_marker: '~',
+            digit_group_separator: '_',
+            code_badge_left: "+-".into(),
+            code_badge_right: "-+".into(),
+        }
+    }
+
+    /// A character set that uses single-column glyphs without corner joints.
+    ///
+    /// This is useful for dense displays on small terminals, as the snippet
+    /// start and the borders of multi-line labels take up less space and are
+    /// less visually busy than with [`Chars::box_drawing()`].
+    pub fn minimal() -> Chars {
+        Chars {
+            snippet_start: "╶".into(),
+            source_border_left: '│',
+            source_border_left_break: '┆',
+
+            note_bullet: '=',
+
+            single_primary_caret: '^',
+            single_secondary_caret: '-',
+            single_uncertain_caret: '┄',
+
+            multi_primary_caret_start: '^',
+            multi_primary_caret_end: '^',
+            multi_secondary_caret_start: '-',
+            multi_secondary_caret_end: '-',
+            multi_top_left: '╷',
+            multi_top: ' ',
+            multi_bottom_left: '╵',
+            multi_bottom: ' ',
+            multi_left: '│',
+
+            pointer_left: '│',
+
+            primary_message_bullet: String::new(),
+            secondary_message_bullet: String::new(),
+
+            source_clip: "…".into(),
+            newline_marker: '⏎',
+            label_count_marker: '×',
+            whitespace_space: '·',
+            whitespace_tab: '→',
+            synthetic_line_marker: '~',
+            digit_group_separator: '_',
+            code_badge_left: "╶".into(),
+            code_badge_right: "╴".into(),
         }
     }
 }
@@ -0,0 +1,472 @@
+//! The passes that rewrite a diagnostic according to the config before it is
+//! rendered.
+//!
+//! Each pass takes ownership of the diagnostic and returns the rewritten
+//! diagnostic, leaving it unchanged if its option is not enabled. The passes
+//! are run in order by [`emit`], on a single copy of the emitted diagnostic.
+//!
+//! [`emit`]: crate::term::emit
+
+use std::ops::Range;
+
+use crate::diagnostic::{Diagnostic, Label, LabelStyle};
+use crate::files::{Error, Files};
+use crate::term::renderer::Renderer;
+use crate::term::views::{is_uncertain, label_location};
+use crate::term::{Config, DisplayStyle, RequirePrimary};
+
+fn is_synthetic<FileId>(label: &Label<FileId>) -> bool {
+    label.synthetic_line.is_some()
+}
+
+/// The mutable labels of the diagnostic and of its steps.
+fn all_labels_mut<FileId>(
+    diagnostic: &mut Diagnostic<FileId>,
+) -> impl Iterator<Item = &mut Label<FileId>> {
+    let step_labels = diagnostic.steps.iter_mut().map(|(label, _)| label);
+    diagnostic.labels.iter_mut().chain(step_labels)
+}
+
+/// Render the first secondary label as a primary label if there are no
+/// primary labels, for [`Config::require_primary`].
+pub fn promote_first_label<FileId>(
+    config: &Config,
+    mut diagnostic: Diagnostic<FileId>,
+) -> Diagnostic<FileId> {
+    match config.require_primary {
+        RequirePrimary::PromoteFirst if diagnostic.is_missing_primary_label() => {
+            // All labels are secondary, so this is the one that starts first.
+            if let Some(label) = diagnostic.primary_label_mut() {
+                label.style = LabelStyle::Primary;
+            }
+        }
+        RequirePrimary::PromoteFirst | RequirePrimary::AllowSecondary => {}
+    }
+    diagnostic
+}
+
+/// Convert the label ranges to zero-based offsets, for
+/// [`Config::byte_offset_base`].
+pub fn rebase_offsets<FileId>(
+    config: &Config,
+    mut diagnostic: Diagnostic<FileId>,
+) -> Diagnostic<FileId> {
+    let base = config.byte_offset_base;
+    if base == 0 {
+        return diagnostic;
+    }
+    for label in all_labels_mut(&mut diagnostic) {
+        // The ranges of synthetic lines are relative to their own text.
+        if is_synthetic(label) {
+            continue;
+        }
+        // Offsets below the base are clamped to the start of the file.
+        label.range = label.range.start.saturating_sub(base)..label.range.end.saturating_sub(base);
+    }
+    diagnostic
+}
+
+/// The file and range of the label to focus on, for [`Config::focus_label`].
+/// This must be found before the labels are merged or reordered.
+pub fn focused_range<FileId: Copy>(
+    config: &Config,
+    diagnostic: &Diagnostic<FileId>,
+) -> Option<(FileId, Range<usize>)> {
+    config
+        .focus_label
+        .and_then(|index| diagnostic.labels.get(index))
+        .filter(|label| !is_synthetic(label))
+        .map(|label| (label.file_id, label.range.clone()))
+}
+
+/// Remove the labels on synthetic lines, which are only rendered by the rich
+/// display style, and are never rendered as steps.
+pub fn remove_synthetic_lines<FileId>(
+    config: &Config,
+    mut diagnostic: Diagnostic<FileId>,
+) -> Diagnostic<FileId> {
+    diagnostic.steps.retain(|(label, _)| !is_synthetic(label));
+    match config.display_style {
+        DisplayStyle::Rich | DisplayStyle::Compact => {}
+        DisplayStyle::Narrow | DisplayStyle::Medium | DisplayStyle::Short | DisplayStyle::Elm => {
+            diagnostic.labels.retain(|label| !is_synthetic(label));
+        }
+    }
+    diagnostic
+}
+
+/// Remove the notes that are empty or only contain whitespace, for
+/// [`Config::skip_empty_notes`].
+pub fn skip_empty_notes<FileId>(
+    config: &Config,
+    mut diagnostic: Diagnostic<FileId>,
+) -> Diagnostic<FileId> {
+    if config.skip_empty_notes {
+        diagnostic.notes.retain(|note| !note.trim().is_empty());
+    }
+    diagnostic
+}
+
+/// Give an empty message the text of the first note, or the configured text,
+/// for [`Config::empty_message_text`].
+pub fn describe_empty_message<FileId>(
+    config: &Config,
+    mut diagnostic: Diagnostic<FileId>,
+) -> Diagnostic<FileId> {
+    match &config.empty_message_text {
+        Some(text) if diagnostic.message.is_empty() => {
+            diagnostic.message = if diagnostic.notes.is_empty() {
+                text.clone()
+            } else {
+                diagnostic.notes.remove(0)
+            };
+        }
+        Some(_) | None => {}
+    }
+    diagnostic
+}
+
+/// Add a note linking to the help of the severity, for
+/// [`Config::severity_help_urls`].
+pub fn link_severity_help<FileId>(
+    config: &Config,
+    diagnostic: Diagnostic<FileId>,
+) -> Diagnostic<FileId> {
+    match config.severity_help_urls.get(&diagnostic.severity) {
+        Some(url) => diagnostic.with_note(format!("help: see {}", url)),
+        None => diagnostic,
+    }
+}
+
+/// Merge the labels that overlap or touch and are otherwise the same, for
+/// [`Config::merge_adjacent_labels`].
+pub fn merge_adjacent_labels<FileId: PartialEq>(
+    config: &Config,
+    mut diagnostic: Diagnostic<FileId>,
+) -> Diagnostic<FileId> {
+    if !config.merge_adjacent_labels {
+        return diagnostic;
+    }
+
+    let is_adjacent = |label: &Label<FileId>, other: &Label<FileId>| {
+        label.style == other.style
+            && label.file_id == other.file_id
+            && label.message == other.message
+            && label.detail == other.detail
+            && label.certainty == other.certainty
+            && label.severity_hint == other.severity_hint
+            && label.message_only == other.message_only
+            && label.display_column.is_none()
+            && other.display_column.is_none()
+            && !is_synthetic(label)
+            && !is_synthetic(other)
+            && label.range.start <= other.range.end
+            && other.range.start <= label.range.end
+    };
+    let mut labels = Vec::<Label<FileId>>::new();
+    for mut label in diagnostic.labels.drain(..) {
+        let mut position = labels.len();
+        // Extending the range may make it touch labels that were not
+        // adjacent to it before, so keep merging until there are none.
+        while let Some(index) = labels.iter().position(|other| is_adjacent(&label, other)) {
+            let other = labels.remove(index);
+            label.range = usize::min(label.range.start, other.range.start)
+                ..usize::max(label.range.end, other.range.end);
+            position = usize::min(position, index);
+        }
+        labels.insert(usize::min(position, labels.len()), label);
+    }
+    diagnostic.labels = labels;
+    diagnostic
+}
+
+/// Qualify the messages of uncertain labels with `(maybe)`, for
+/// [`Config::certainty_threshold`].
+pub fn qualify_uncertain_labels<FileId>(
+    config: &Config,
+    mut diagnostic: Diagnostic<FileId>,
+) -> Diagnostic<FileId> {
+    for label in all_labels_mut(&mut diagnostic) {
+        if is_uncertain(config, label) {
+            label.message = if label.message.is_empty() {
+                String::from("(maybe)")
+            } else {
+                format!("{} (maybe)", label.message)
+            };
+        }
+    }
+    diagnostic
+}
+
+/// Prefix the messages of primary labels drawn with a single caret with their
+/// column number, for [`Config::annotate_caret_column`].
+pub fn annotate_caret_columns<'files, F: Files<'files>>(
+    config: &Config,
+    files: &'files F,
+    mut diagnostic: Diagnostic<F::FileId>,
+) -> Result<Diagnostic<F::FileId>, Error> {
+    if !config.annotate_caret_column {
+        return Ok(diagnostic);
+    }
+    for label in all_labels_mut(&mut diagnostic) {
+        if label.style != LabelStyle::Primary || is_synthetic(label) || label.message_only {
+            continue;
+        }
+        // Only labels that are drawn with a single caret are annotated.
+        let source = files.source(label.file_id)?;
+        let is_single_point = label.display_column.is_some()
+            || match source.as_ref().get(label.range.clone()) {
+                Some(text) => text.chars().nth(1).is_none() && !text.contains('\n'),
+                None => false,
+            };
+        if is_single_point {
+            let column_number = label_location(files, label)?.column_number;
+            label.message = if label.message.is_empty() {
+                format!("(col {})", column_number)
+            } else {
+                format!("(col {}) {}", column_number, label.message)
+            };
+        }
+    }
+    Ok(diagnostic)
+}
+
+/// Replace identical labels with a single label with a count, for
+/// [`Config::coalesce_identical_labels`].
+pub fn coalesce_identical_labels<FileId: PartialEq>(
+    config: &Config,
+    mut diagnostic: Diagnostic<FileId>,
+) -> Diagnostic<FileId> {
+    if !config.coalesce_identical_labels {
+        return diagnostic;
+    }
+
+    let is_identical = |label: &Label<FileId>, other: &Label<FileId>| {
+        label.style == other.style
+            && label.file_id == other.file_id
+            && label.range == other.range
+            && label.message == other.message
+    };
+    let mut labels = Vec::<(Label<FileId>, usize)>::new();
+    for label in diagnostic.labels.drain(..) {
+        match labels
+            .iter_mut()
+            .find(|(other, _)| is_identical(&label, other))
+        {
+            Some((_, count)) => *count += 1,
+            None => labels.push((label, 1)),
+        }
+    }
+    diagnostic.labels = labels
+        .into_iter()
+        .map(|(mut label, count)| {
+            if count > 1 {
+                let marker = config.chars.label_count_marker;
+                label.message = if label.message.is_empty() {
+                    format!("({}{})", marker, count)
+                } else {
+                    format!("{} ({}{})", label.message, marker, count)
+                };
+            }
+            label
+        })
+        .collect();
+    diagnostic
+}
+
+/// Replace the secondary labels with a note listing their locations, for
+/// [`Config::fold_secondary_labels`].
+pub fn fold_secondary_labels<'files, F: Files<'files>>(
+    config: &Config,
+    files: &'files F,
+    mut diagnostic: Diagnostic<F::FileId>,
+) -> Result<Diagnostic<F::FileId>, Error> {
+    let has_style = |style| diagnostic.labels.iter().any(|label| label.style == style);
+    if !config.fold_secondary_labels
+        || !has_style(LabelStyle::Primary)
+        || !has_style(LabelStyle::Secondary)
+    {
+        return Ok(diagnostic);
+    }
+
+    let mut locators = Vec::new();
+    for label in &diagnostic.labels {
+        if label.style == LabelStyle::Primary {
+            continue;
+        }
+        let name = files.name(label.file_id)?.to_string();
+        let name = if name.is_empty() {
+            config.anonymous_file_name.clone()
+        } else {
+            name
+        };
+        locators.push(match label.synthetic_line {
+            Some(_) => name,
+            None => {
+                let location = label_location(files, label)?;
+                format!(
+                    "{}:{}:{}",
+                    name, location.line_number, location.column_number,
+                )
+            }
+        });
+    }
+    diagnostic
+        .labels
+        .retain(|label| label.style == LabelStyle::Primary);
+    let summary = format!(
+        "{} and {} related location{}: {}",
+        config.chars.source_clip,
+        locators.len(),
+        if locators.len() == 1 { "" } else { "s" },
+        locators.join(", "),
+    );
+    diagnostic.notes.insert(0, summary);
+    Ok(diagnostic)
+}
+
+/// Remove the labels of the snippet blocks past the maximum, keeping the
+/// blocks with primary labels first, for [`Config::max_snippet_blocks`].
+pub fn cap_snippet_blocks<'files, F: Files<'files>>(
+    config: &Config,
+    files: &'files F,
+    mut diagnostic: Diagnostic<F::FileId>,
+) -> Result<Diagnostic<F::FileId>, Error> {
+    let max_blocks = match config.max_snippet_blocks {
+        Some(max_blocks) => max_blocks,
+        None => return Ok(diagnostic),
+    };
+
+    let primary_file_ids = diagnostic
+        .labels
+        .iter()
+        .filter(|label| label.style == LabelStyle::Primary)
+        .map(|label| label.file_id)
+        .collect::<Vec<_>>();
+
+    // Group the labels that are rendered as source snippets into blocks of
+    // lines, in the order that their files are rendered in.
+    let mut file_ids = Vec::new();
+    let mut spans = Vec::new();
+    for (label_index, label) in diagnostic.labels.iter().enumerate() {
+        if is_synthetic(label)
+            || (config.inline_cross_file_refs
+                && label.style == LabelStyle::Secondary
+                && !primary_file_ids.is_empty()
+                && !primary_file_ids.contains(&label.file_id))
+        {
+            continue;
+        }
+        let start_line_index = files.line_index(label.file_id, label.range.start)?;
+        let end_line_index = match label.display_column {
+            Some(_) => start_line_index,
+            None => files.line_index(label.file_id, label.range.end)?,
+        };
+        let file_index = match file_ids.iter().position(|id| *id == label.file_id) {
+            Some(file_index) => file_index,
+            None => {
+                file_ids.push(label.file_id);
+                file_ids.len() - 1
+            }
+        };
+        spans.push((
+            file_index,
+            start_line_index.saturating_sub(config.before_label_lines),
+            end_line_index + config.after_label_lines,
+            label_index,
+        ));
+    }
+    spans.sort_by_key(|&(file_index, start, end, _)| (file_index, start, end));
+
+    // Blocks of `(labels, has_primary)`.
+    let mut blocks = Vec::<(Vec<usize>, bool)>::new();
+    let mut last_span = None;
+    for (file_index, start, end, label_index) in spans {
+        let is_primary = diagnostic.labels[label_index].style == LabelStyle::Primary;
+        match (last_span, blocks.last_mut()) {
+            // Lines separated by a single line are rendered together.
+            (Some((last_file_index, last_end)), Some(block))
+                if last_file_index == file_index && start <= last_end + 2 =>
+            {
+                block.0.push(label_index);
+                block.1 |= is_primary;
+                last_span = Some((file_index, usize::max(last_end, end)));
+            }
+            _ => {
+                blocks.push((vec![label_index], is_primary));
+                last_span = Some((file_index, end));
+            }
+        }
+    }
+
+    if blocks.len() <= max_blocks {
+        return Ok(diagnostic);
+    }
+
+    let mut hidden_labels = vec![false; diagnostic.labels.len()];
+    for (labels, _) in &blocks {
+        for &label_index in labels {
+            hidden_labels[label_index] = true;
+        }
+    }
+    let kept_blocks = blocks
+        .iter()
+        .filter(|(_, has_primary)| *has_primary)
+        .chain(blocks.iter().filter(|(_, has_primary)| !*has_primary))
+        .take(max_blocks);
+    for (labels, _) in kept_blocks {
+        for &label_index in labels {
+            hidden_labels[label_index] = false;
+        }
+    }
+
+    let mut hidden_labels = hidden_labels.into_iter();
+    diagnostic
+        .labels
+        .retain(|_| !hidden_labels.next().unwrap_or(false));
+    let num_hidden = blocks.len() - max_blocks;
+    diagnostic.notes.insert(
+        0,
+        format!(
+            "{} {} more location{} not shown",
+            config.chars.source_clip,
+            num_hidden,
+            if num_hidden == 1 { "" } else { "s" },
+        ),
+    );
+    Ok(diagnostic)
+}
+
+/// Whether the source lines of the diagnostic would not fit next to the
+/// gutter, so that it should be rendered in the short format instead, for
+/// [`Config::min_width_fallback`].
+pub fn needs_min_width_fallback<'files, F: Files<'files>>(
+    config: &Config,
+    files: &'files F,
+    diagnostic: &Diagnostic<F::FileId>,
+    renderer: &Renderer<'_, '_>,
+) -> Result<bool, Error> {
+    const MIN_SOURCE_WIDTH: usize = 8;
+
+    let has_gutter = match config.display_style {
+        DisplayStyle::Rich | DisplayStyle::Compact | DisplayStyle::Narrow | DisplayStyle::Elm => {
+            true
+        }
+        DisplayStyle::Medium | DisplayStyle::Short => false,
+    };
+    let terminal_width = match (config.min_width_fallback, has_gutter, config.terminal_width) {
+        (true, true, Some(terminal_width)) => terminal_width,
+        _ => return Ok(false),
+    };
+
+    let mut outer_padding = 1;
+    for label in diagnostic
+        .labels
+        .iter()
+        .filter(|label| !is_synthetic(label))
+    {
+        let line_number = label_location(files, label)?.line_number;
+        outer_padding = std::cmp::max(outer_padding, line_number.to_string().len());
+    }
+    Ok(terminal_width < renderer.gutter_width(outer_padding) + MIN_SOURCE_WIDTH)
+}
@@ -1,10 +1,12 @@
 use std::io::{self, Write};
 use std::ops::Range;
-use termcolor::{ColorSpec, WriteColor};
+use termcolor::ColorSpec;
 
 use crate::diagnostic::{LabelStyle, Severity};
 use crate::files::{Error, Location};
-use crate::term::{Chars, Config, Styles};
+use crate::term::{
+    truncate_to_width, Chars, Config, GutterAlign, SeverityCase, StyledSink, Styles,
+};
 
 /// The 'location focus' of a source code snippet.
 pub struct Locus {
@@ -14,16 +16,42 @@ pub struct Locus {
     pub location: Location,
 }
 
-/// Single-line label, with an optional message.
+/// Single-line label, with an optional message, and hints about how the label
+/// should be rendered.
 ///
 /// ```text
 /// ^^^^^^^^^ blah blah
 /// ```
-pub type SingleLabel<'diagnostic> = (LabelStyle, Range<usize>, &'diagnostic str);
+pub type SingleLabel<'diagnostic> = (LabelStyle, Range<usize>, &'diagnostic str, LabelHints);
+
+/// Hints about how a single-line label should be rendered, in addition to its
+/// style.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct LabelHints {
+    /// Whether the label is uncertain and should be rendered with dashed carets.
+    pub uncertain: bool,
+    /// The severity whose colors the label should be rendered with.
+    pub severity: Option<Severity>,
+    /// Whether the label should be rendered without carets, with its message
+    /// connected to the start of its range.
+    pub message_only: bool,
+}
+
+/// The user-facing name of a severity.
+pub fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Bug => "bug",
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Help => "help",
+        Severity::Note => "note",
+    }
+}
 
 /// A multi-line label to render.
 ///
 /// Locations are relative to the start of where the source code is rendered.
+#[derive(Clone)]
 pub enum MultiLabel<'diagnostic> {
     /// Multi-line label top.
     /// The contained value indicates where the label starts.
@@ -54,6 +82,24 @@ pub enum MultiLabel<'diagnostic> {
     Bottom(usize, &'diagnostic str),
 }
 
+/// The style of a fragment of a row in a side-by-side layout.
+#[derive(Copy, Clone)]
+pub enum SideStyle {
+    /// Unstyled source code or padding.
+    Source,
+    /// A line number in the outer gutter.
+    LineNumber,
+    /// The border of a snippet.
+    Border,
+    /// The carets or message of a label.
+    Label(LabelStyle),
+}
+
+/// A styled fragment of a row in a side-by-side layout.
+///
+/// The text of the fragment must not contain tabs or line breaks.
+pub type SidePart = (SideStyle, String);
+
 #[derive(Copy, Clone)]
 enum VerticalBound {
     Top,
@@ -109,17 +155,50 @@ type Underline = (LabelStyle, VerticalBound);
 ///
 /// Filler text from http://www.cupcakeipsum.com
 pub struct Renderer<'writer, 'config> {
-    writer: &'writer mut dyn WriteColor,
+    writer: &'writer mut dyn StyledSink,
     config: &'config Config,
+    /// Whether all of the output is currently dimmed.
+    dimmed: bool,
+    /// The index of the diagnostic in its batch and the size of the batch,
+    /// which prefix the header if [`Config::show_index`] is enabled.
+    index: Option<(usize, usize)>,
 }
 
 impl<'writer, 'config> Renderer<'writer, 'config> {
-    /// Construct a renderer from the given writer and config.
+    /// Construct a renderer from the given sink and config.
     pub fn new(
-        writer: &'writer mut dyn WriteColor,
+        writer: &'writer mut dyn StyledSink,
         config: &'config Config,
     ) -> Renderer<'writer, 'config> {
-        Renderer { writer, config }
+        Renderer {
+            writer,
+            config,
+            dimmed: false,
+            index: None,
+        }
+    }
+
+    /// Set the index of the diagnostic in its batch, and the size of the batch.
+    pub fn set_index(&mut self, index: Option<(usize, usize)>) {
+        self.index = index;
+    }
+
+    /// The width of the gutter to the left of source lines, including the
+    /// left-hand border.
+    pub fn gutter_width(&self, outer_padding: usize) -> usize {
+        self.line_number_width(outer_padding)
+            + self.config.gutter_padding_left
+            + 1
+            + self.config.gutter_padding_right
+    }
+
+    /// Dim all of the output that is rendered after this, or stop doing so.
+    pub fn set_dimmed(&mut self, dimmed: bool) -> Result<(), Error> {
+        if self.dimmed != dimmed {
+            self.dimmed = dimmed;
+            self.reset()?;
+        }
+        Ok(())
     }
 
     fn chars(&self) -> &'config Chars {
@@ -130,6 +209,28 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
         &self.config.styles
     }
 
+    /// The bullet to write in front of the message of a label.
+    fn message_bullet(&self, label_style: LabelStyle) -> &'config str {
+        match label_style {
+            LabelStyle::Primary => &self.chars().primary_message_bullet,
+            LabelStyle::Secondary => &self.chars().secondary_message_bullet,
+        }
+    }
+
+    /// The color of a single-line label, using the primary label color of
+    /// its severity hint if it has one.
+    fn label_color(
+        &self,
+        severity: Severity,
+        label_style: LabelStyle,
+        hints: LabelHints,
+    ) -> &'config ColorSpec {
+        match hints.severity {
+            Some(severity) => self.styles().label(severity, LabelStyle::Primary),
+            None => self.styles().label(severity, label_style),
+        }
+    }
+
     /// Diagnostic header, with severity, code, and message.
     ///
     /// ```text
@@ -142,6 +243,47 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
         code: Option<&str>,
         message: &str,
     ) -> Result<(), Error> {
+        let code = code.filter(|code| !code.is_empty());
+        let truncated_code;
+        let code = match (code, self.config.max_code_width) {
+            (Some(code), Some(max_width)) => {
+                truncated_code = truncate_to_width(code, max_width, &self.chars().source_clip);
+                Some(truncated_code.as_str())
+            }
+            (code, _) => code,
+        };
+
+        // Write code badge
+        //
+        // ```text
+        // ┌─ E0001 ─┐
+        // ```
+        let code = match code {
+            Some(code) if self.config.code_badge_line => {
+                self.set_color(self.styles().header(severity))?;
+                write!(
+                    self,
+                    "{} {} {}",
+                    self.chars().code_badge_left,
+                    code,
+                    self.chars().code_badge_right,
+                )?;
+                self.reset()?;
+                writeln!(self)?;
+                None
+            }
+            code => code,
+        };
+
+        // Write index in the batch
+        //
+        // ```text
+        // [2/3]
+        // ```
+        if let (true, Some((index, len))) = (self.config.show_index, self.index) {
+            write!(self, "[{}/{}] ", index + 1, len)?;
+        }
+
         // Write locus
         //
         // ```text
@@ -152,18 +294,27 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
             write!(self, ": ")?;
         }
 
+        // Write severity icon
+        //
+        // ```text
+        // ❌
+        // ```
+        let icons = self.config.severity_icons.as_ref();
+        if let Some(icon) = icons.and_then(|icons| icons.get(&severity)) {
+            write!(self, "{} ", icon)?;
+        }
+
         // Write severity name
         //
         // ```text
         // error
         // ```
         self.set_color(self.styles().header(severity))?;
-        match severity {
-            Severity::Bug => write!(self, "bug")?,
-            Severity::Error => write!(self, "error")?,
-            Severity::Warning => write!(self, "warning")?,
-            Severity::Help => write!(self, "help")?,
-            Severity::Note => write!(self, "note")?,
+        let name = severity_name(severity);
+        match self.config.severity_case {
+            SeverityCase::Lower => write!(self, "{}", name)?,
+            SeverityCase::Upper => write!(self, "{}", name.to_uppercase())?,
+            SeverityCase::Title => write!(self, "{}{}", name[..1].to_uppercase(), &name[1..])?,
         }
 
         // Write error code
@@ -171,7 +322,7 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
         // ```text
         // [E0001]
         // ```
-        if let Some(code) = &code.filter(|code| !code.is_empty()) {
+        if let Some(code) = code {
             write!(self, "[{}]", code)?;
         }
 
@@ -189,12 +340,117 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
         Ok(())
     }
 
+    /// Section header, naming a group of diagnostics with the same severity.
+    ///
+    /// ```text
+    /// Errors
+    /// ```
+    pub fn render_section_header(&mut self, severity: Severity) -> Result<(), Error> {
+        self.set_color(self.styles().header(severity))?;
+        match severity {
+            Severity::Bug => write!(self, "Bugs")?,
+            Severity::Error => write!(self, "Errors")?,
+            Severity::Warning => write!(self, "Warnings")?,
+            Severity::Help => write!(self, "Help")?,
+            Severity::Note => write!(self, "Notes")?,
+        }
+        self.reset()?;
+        writeln!(self)?;
+
+        Ok(())
+    }
+
+    /// Summary of the number of diagnostics of each severity.
+    ///
+    /// ```text
+    /// 2 errors, 1 warning
+    /// ```
+    pub fn render_summary(&mut self, counts: &[(Severity, usize)]) -> Result<(), Error> {
+        for (index, &(severity, count)) in counts.iter().enumerate() {
+            if index > 0 {
+                write!(self, ", ")?;
+            }
+
+            let noun = match severity {
+                Severity::Bug => "bug",
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+                Severity::Help => "help message",
+                Severity::Note => "note",
+            };
+            let plural = if count == 1 { "" } else { "s" };
+            if count > 0 {
+                self.set_color(self.styles().header(severity))?;
+                write!(self, "{} {}{}", count, noun, plural)?;
+                self.reset()?;
+            } else {
+                write!(self, "{} {}{}", count, noun, plural)?;
+            }
+        }
+        writeln!(self)?;
+
+        Ok(())
+    }
+
+    /// A row of a table, with each cell but the last padded to the width of its
+    /// column. The header row is written if no severity is given.
+    ///
+    /// ```text
+    /// error     E0308  main.rs:2:9  mismatched types
+    /// ```
+    pub fn render_table_row(
+        &mut self,
+        widths: &[usize],
+        column_gap: usize,
+        severity: Option<Severity>,
+        cells: &[&str],
+    ) -> Result<(), Error> {
+        use unicode_width::UnicodeWidthStr;
+
+        for (index, cell) in cells.iter().enumerate() {
+            let spec = match severity {
+                None => Some(&self.styles().header_message),
+                Some(severity) if index == 0 => Some(self.styles().header(severity)),
+                Some(_) => None,
+            };
+            if let Some(spec) = spec {
+                self.set_color(spec)?;
+            }
+            write!(self, "{}", cell)?;
+            if spec.is_some() {
+                self.reset()?;
+            }
+
+            if let Some(width) = widths.get(index) {
+                let padding = width.saturating_sub(cell.width()) + column_gap;
+                write!(self, "{: >width$}", "", width = padding)?;
+            }
+        }
+        writeln!(self)?;
+
+        Ok(())
+    }
+
     /// Empty line.
     pub fn render_empty(&mut self) -> Result<(), Error> {
         writeln!(self)?;
         Ok(())
     }
 
+    /// The header of a step, with its number and description.
+    ///
+    /// ```text
+    /// 1. value created here
+    /// ```
+    pub fn render_step_header(&mut self, number: usize, description: &str) -> Result<(), Error> {
+        self.set_color(&self.styles().note_bullet)?;
+        write!(self, "{}.", number)?;
+        self.reset()?;
+        writeln!(self, " {}", description)?;
+
+        Ok(())
+    }
+
     /// Top left border and locus.
     ///
     /// ```text
@@ -219,16 +475,45 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
         Ok(())
     }
 
+    /// Top left border and file name, for snippets that do not have a
+    /// location in the file.
+    ///
+    /// ```text
+    /// ┌─ expanded.rs
+    /// ```
+    pub fn render_snippet_start_name(
+        &mut self,
+        outer_padding: usize,
+        name: &str,
+    ) -> Result<(), Error> {
+        self.outer_gutter(outer_padding)?;
+
+        self.set_color(&self.styles().source_border)?;
+        write!(self, "{}", self.chars().snippet_start)?;
+        self.reset()?;
+
+        write!(self, " ")?;
+        self.snippet_name(name)?;
+
+        writeln!(self)?;
+
+        Ok(())
+    }
+
     /// A line of source code.
     ///
     /// ```text
     /// 10 │   │ muffin. Halvah croissant candy canes bonbon candy. Apple pie jelly
     ///    │ ╭─│─────────^
     /// ```
+    ///
+    /// Lines without a line number are synthetic lines, which are marked with
+    /// [`Chars::synthetic_line_marker`] instead.
+    #[allow(clippy::too_many_arguments)]
     pub fn render_snippet_source(
         &mut self,
         outer_padding: usize,
-        line_number: usize,
+        line_number: Option<usize>,
         source: &str,
         severity: Severity,
         single_labels: &[SingleLabel<'_>],
@@ -239,6 +524,79 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
         // FIXME: Use the number of trimmed placeholders when rendering single line carets
         let source = source.trim_end_matches(['\n', '\r', '\0'].as_ref());
 
+        // Write the messages of single labels above the source as captions, if
+        // requested, leaving only their carets to be written underneath
+        //
+        // ```text
+        //   │     first mutable borrow occurs here
+        //   │     │ second mutable borrow occurs here
+        //   │     │ │
+        // ```
+        let uncaptioned_labels;
+        let single_labels = if self.config.message_above_underline {
+            let is_caption =
+                |message: &str, hints: &LabelHints| !message.is_empty() && !hints.message_only;
+            let mut caption_labels = single_labels
+                .iter()
+                .filter(|(_, _, message, hints)| is_caption(message, hints))
+                .cloned()
+                .collect::<Vec<_>>();
+            caption_labels.sort_by_key(|(_, range, _, _)| range.start);
+
+            if let Some((_, last_range, _, _)) = caption_labels.last() {
+                let max_label_start = last_range.start;
+                for (index, (label_style, range, message, hints)) in
+                    caption_labels.iter().enumerate()
+                {
+                    self.outer_gutter(outer_padding)?;
+                    self.border_left()?;
+                    self.inner_gutter(severity, num_multi_labels, multi_labels)?;
+                    self.gutter_padding()?;
+                    self.caret_pointers(
+                        severity,
+                        max_label_start,
+                        &caption_labels[..index],
+                        None,
+                        source
+                            .char_indices()
+                            .take_while(|(byte_index, _)| *byte_index < range.start),
+                    )?;
+                    self.set_color(self.label_color(severity, *label_style, *hints))?;
+                    write!(self, "{}{}", self.message_bullet(*label_style), message)?;
+                    self.reset()?;
+                    writeln!(self)?;
+                }
+
+                self.outer_gutter(outer_padding)?;
+                self.border_left()?;
+                self.inner_gutter(severity, num_multi_labels, multi_labels)?;
+                self.gutter_padding()?;
+                self.caret_pointers(
+                    severity,
+                    max_label_start,
+                    &caption_labels,
+                    None,
+                    source.char_indices(),
+                )?;
+                writeln!(self)?;
+            }
+
+            uncaptioned_labels = single_labels
+                .iter()
+                .map(|(label_style, range, message, hints)| {
+                    let message = if is_caption(message, hints) {
+                        ""
+                    } else {
+                        *message
+                    };
+                    (*label_style, range.clone(), message, *hints)
+                })
+                .collect::<Vec<_>>();
+            &uncaptioned_labels[..]
+        } else {
+            single_labels
+        };
+
         // get the byte index of the first non-whitespace character
         let text_start = source.find(|c| !char::is_whitespace(c)).unwrap_or(0);
 
@@ -286,19 +644,44 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
             }
 
             // Write source text
-            write!(self, " ")?;
-            let mut highlighted = false;
+            self.gutter_padding()?;
+            let mut highlighted = None;
+
+            // The ranges of characters in which whitespace is revealed
+            let reveal_ranges = match self.config.reveal_whitespace_margin {
+                None => Vec::new(),
+                Some(margin) => {
+                    let char_index = |byte_index: usize| {
+                        source
+                            .char_indices()
+                            .take_while(|(index, _)| *index < byte_index)
+                            .count()
+                    };
+                    single_labels
+                        .iter()
+                        .map(|(_, range, _, _)| {
+                            char_index(range.start).saturating_sub(margin)
+                                ..(char_index(range.end) + margin)
+                        })
+                        .collect::<Vec<_>>()
+                }
+            };
 
             // iterate over all characters of source code
-            for (metrics, ch) in self.char_metrics(source.char_indices()) {
+            for (char_index, (metrics, ch)) in self.char_metrics(source.char_indices()).enumerate()
+            {
                 let column_range = metrics.byte_index..(metrics.byte_index + ch.len_utf8());
+                let revealed = reveal_ranges
+                    .iter()
+                    .any(|reveal_range| reveal_range.contains(&char_index));
 
                 /*
                 A character should be highlighted only if it is part of a primary single label which does not span the whole line
                 A label already spans the whole line if it does not cover leading/trailing whitespace.
                 */
-                let should_highlight = single_labels.iter().any(|(ls, range, _)| {
+                let highlight = single_labels.iter().find(|(ls, range, _, hints)| {
                     *ls == LabelStyle::Primary
+                        && !hints.message_only
                         // is this at the current position
                         && is_overlapping(range, &column_range)
                         // is this not a whole line label
@@ -307,23 +690,36 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
                             && range.end >= text_range.end
                         )
                 });
+                let highlight = highlight.map(|(_, _, _, hints)| {
+                    self.label_color(severity, LabelStyle::Primary, *hints)
+                });
 
                 // Set the source color if we are in a primary label
-                if should_highlight && !highlighted {
-                    self.set_color(self.styles().label(severity, LabelStyle::Primary))?;
-                    highlighted = true;
-                } else if !should_highlight && highlighted {
-                    self.reset()?;
-                    highlighted = false;
+                match highlight {
+                    Some(spec) if highlighted != Some(spec) => {
+                        self.set_color(spec)?;
+                        highlighted = Some(spec);
+                    }
+                    None if highlighted.is_some() => {
+                        self.reset()?;
+                        highlighted = None;
+                    }
+                    Some(_) | None => {}
                 }
 
                 // actually write the character
                 match ch {
+                    ' ' if revealed => write!(self, "{}", self.chars().whitespace_space)?,
+                    '\t' if revealed => {
+                        write!(self, "{}", self.chars().whitespace_tab)?;
+                        (1..metrics.unicode_width).try_for_each(|_| write!(self, " "))?;
+                    }
+                    '\t' if self.config.preserve_tabs_in_output => write!(self, "\t")?,
                     '\t' => (0..metrics.unicode_width).try_for_each(|_| write!(self, " "))?,
                     _ => write!(self, "{}", ch)?,
                 }
             }
-            if highlighted {
+            if highlighted.is_some() {
                 self.reset()?;
             }
             writeln!(self)?;
@@ -384,11 +780,15 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
             let mut trailing_label = None;
 
             for (label_index, label) in single_labels.iter().enumerate() {
-                let (_, range, message) = label;
+                let (_, range, message, hints) = label;
                 if !message.is_empty() {
                     num_messages += 1;
                 }
                 max_label_start = std::cmp::max(max_label_start, range.start);
+                // Labels without carets are always rendered as hanging labels.
+                if hints.message_only {
+                    continue;
+                }
                 max_label_end = std::cmp::max(max_label_end, range.end);
                 // This is a candidate for the trailing label, so let's record it.
                 if range.end == max_label_end {
@@ -399,14 +799,16 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
                     }
                 }
             }
-            if let Some((trailing_label_index, (_, trailing_range, _))) = trailing_label {
+            if let Some((trailing_label_index, (_, trailing_range, _, _))) = trailing_label {
                 // Check to see if the trailing label candidate overlaps any of
                 // the other labels on the current line.
                 if single_labels
                     .iter()
                     .enumerate()
                     .filter(|(label_index, _)| *label_index != trailing_label_index)
-                    .any(|(_, (_, range, _))| is_overlapping(trailing_range, range))
+                    .any(|(_, (_, range, _, hints))| {
+                        !hints.message_only && is_overlapping(trailing_range, range)
+                    })
                 {
                     // If it does, we'll instead want to render it below the
                     // carets along with the other hanging labels.
@@ -414,76 +816,92 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
                 }
             }
 
-            // Write a line of carets
+            // Write a line of carets, unless all of the labels are rendered
+            // without carets
             //
             // ```text
             //   │ ^^^^^^  -------^^^^^^^^^-------^^^^^----- ^^^^ trailing label message
             // ```
-            self.outer_gutter(outer_padding)?;
-            self.border_left()?;
-            self.inner_gutter(severity, num_multi_labels, multi_labels)?;
-            write!(self, " ")?;
+            let has_carets = single_labels
+                .iter()
+                .any(|(_, _, _, hints)| !hints.message_only);
+            if has_carets {
+                self.outer_gutter(outer_padding)?;
+                self.border_left()?;
+                self.inner_gutter(severity, num_multi_labels, multi_labels)?;
+                self.gutter_padding()?;
 
-            let mut previous_label_style = None;
-            let placeholder_metrics = Metrics {
-                byte_index: source.len(),
-                unicode_width: 1,
-            };
-            for (metrics, ch) in self
-                .char_metrics(source.char_indices())
-                // Add a placeholder source column at the end to allow for
-                // printing carets at the end of lines, eg:
-                //
-                // ```text
-                // 1 │ Hello world!
-                //   │             ^
-                // ```
-                .chain(std::iter::once((placeholder_metrics, '\0')))
-            {
-                // Find the current label style at this column
-                let column_range = metrics.byte_index..(metrics.byte_index + ch.len_utf8());
-                let current_label_style = single_labels
-                    .iter()
-                    .filter(|(_, range, _)| is_overlapping(range, &column_range))
-                    .map(|(label_style, _, _)| *label_style)
-                    .max_by_key(label_priority_key);
-
-                // Update writer style if necessary
-                if previous_label_style != current_label_style {
-                    match current_label_style {
-                        None => self.reset()?,
-                        Some(label_style) => {
-                            self.set_color(self.styles().label(severity, label_style))?;
+                let mut previous_label_style = None;
+                let placeholder_metrics = Metrics {
+                    byte_index: source.len(),
+                    unicode_width: 1,
+                };
+                for (metrics, ch) in self
+                    .char_metrics(source.char_indices())
+                    // Add a placeholder source column at the end to allow for
+                    // printing carets at the end of lines, eg:
+                    //
+                    // ```text
+                    // 1 │ Hello world!
+                    //   │             ^
+                    // ```
+                    .chain(std::iter::once((placeholder_metrics, '\0')))
+                {
+                    // Find the current label style at this column
+                    let column_range = metrics.byte_index..(metrics.byte_index + ch.len_utf8());
+                    let current_label = single_labels
+                        .iter()
+                        .filter(|(_, range, _, hints)| {
+                            !hints.message_only && is_overlapping(range, &column_range)
+                        })
+                        .map(|(label_style, _, _, hints)| (*label_style, *hints))
+                        .max_by_key(|(label_style, _)| label_priority_key(label_style));
+                    let current_label_style =
+                        current_label.map(|(label_style, hints)| (label_style, hints.severity));
+
+                    // Update writer style if necessary
+                    if previous_label_style != current_label_style {
+                        match current_label {
+                            None => self.reset()?,
+                            Some((label_style, hints)) => {
+                                self.set_color(self.label_color(severity, label_style, hints))?;
+                            }
                         }
                     }
-                }
 
-                let caret_ch = match current_label_style {
-                    Some(LabelStyle::Primary) => Some(self.chars().single_primary_caret),
-                    Some(LabelStyle::Secondary) => Some(self.chars().single_secondary_caret),
-                    // Only print padding if we are before the end of the last single line caret
-                    None if metrics.byte_index < max_label_end => Some(' '),
-                    None => None,
-                };
-                if let Some(caret_ch) = caret_ch {
-                    // FIXME: improve rendering of carets between character boundaries
-                    (0..metrics.unicode_width).try_for_each(|_| write!(self, "{}", caret_ch))?;
-                }
+                    let caret_ch = match current_label {
+                        Some((_, hints)) if hints.uncertain => {
+                            Some(self.chars().single_uncertain_caret)
+                        }
+                        Some((LabelStyle::Primary, _)) => Some(self.chars().single_primary_caret),
+                        Some((LabelStyle::Secondary, _)) => {
+                            Some(self.chars().single_secondary_caret)
+                        }
+                        // Only print padding if we are before the end of the last single line caret
+                        None if metrics.byte_index < max_label_end => Some(' '),
+                        None => None,
+                    };
+                    if let Some(caret_ch) = caret_ch {
+                        // FIXME: improve rendering of carets between character boundaries
+                        (0..metrics.unicode_width)
+                            .try_for_each(|_| write!(self, "{}", caret_ch))?;
+                    }
 
-                previous_label_style = current_label_style;
-            }
-            // Reset style if it was previously set
-            if previous_label_style.is_some() {
-                self.reset()?;
-            }
-            // Write first trailing label message
-            if let Some((_, (label_style, _, message))) = trailing_label {
-                write!(self, " ")?;
-                self.set_color(self.styles().label(severity, *label_style))?;
-                write!(self, "{}", message)?;
-                self.reset()?;
+                    previous_label_style = current_label_style;
+                }
+                // Reset style if it was previously set
+                if previous_label_style.is_some() {
+                    self.reset()?;
+                }
+                // Write first trailing label message
+                if let Some((_, (label_style, _, message, hints))) = trailing_label {
+                    write!(self, " ")?;
+                    self.set_color(self.label_color(severity, *label_style, *hints))?;
+                    write!(self, "{}{}", self.message_bullet(*label_style), message)?;
+                    self.reset()?;
+                }
+                writeln!(self)?;
             }
-            writeln!(self)?;
 
             // Write hanging labels pointing to carets
             //
@@ -502,7 +920,7 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
                 self.outer_gutter(outer_padding)?;
                 self.border_left()?;
                 self.inner_gutter(severity, num_multi_labels, multi_labels)?;
-                write!(self, " ")?;
+                self.gutter_padding()?;
                 self.caret_pointers(
                     severity,
                     max_label_start,
@@ -519,13 +937,13 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
                 //   │     first borrow later used by call
                 //   │     help: some help here
                 // ```
-                for (label_style, range, message) in
+                for (label_style, range, message, hints) in
                     hanging_labels(single_labels, trailing_label).rev()
                 {
                     self.outer_gutter(outer_padding)?;
                     self.border_left()?;
                     self.inner_gutter(severity, num_multi_labels, multi_labels)?;
-                    write!(self, " ")?;
+                    self.gutter_padding()?;
                     self.caret_pointers(
                         severity,
                         max_label_start,
@@ -535,8 +953,8 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
                             .char_indices()
                             .take_while(|(byte_index, _)| *byte_index < range.start),
                     )?;
-                    self.set_color(self.styles().label(severity, *label_style))?;
-                    write!(self, "{}", message)?;
+                    self.set_color(self.label_color(severity, *label_style, *hints))?;
+                    write!(self, "{}{}", self.message_bullet(*label_style), message)?;
                     self.reset()?;
                     writeln!(self)?;
                 }
@@ -613,6 +1031,93 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
         Ok(())
     }
 
+    /// The detail of a label, underneath the labels of a source line.
+    ///
+    /// ```text
+    ///   │ │     the value is moved here,
+    ///   │ │     so it can not be used again
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_snippet_detail(
+        &mut self,
+        outer_padding: usize,
+        source: &str,
+        severity: Severity,
+        num_multi_labels: usize,
+        multi_labels: &[(usize, LabelStyle, MultiLabel<'_>)],
+        label_style: LabelStyle,
+        hints: LabelHints,
+        start: usize,
+        detail: &str,
+    ) -> Result<(), Error> {
+        let source = source.trim_end_matches(['\n', '\r', '\0'].as_ref());
+        let column = self
+            .char_metrics(source.char_indices())
+            .take_while(|(metrics, _)| metrics.byte_index < start)
+            .map(|(metrics, _)| metrics.unicode_width)
+            .sum::<usize>();
+
+        for line in detail.lines() {
+            self.outer_gutter(outer_padding)?;
+            self.border_left()?;
+
+            // Write inner gutter, with lines for the multi-line labels that
+            // continue past the current line.
+            let mut multi_labels_iter = multi_labels.iter().peekable();
+            for label_column in 0..num_multi_labels {
+                match multi_labels_iter.peek() {
+                    Some((label_index, ls, label)) if *label_index == label_column => {
+                        match label {
+                            MultiLabel::Top(..) | MultiLabel::Left => {
+                                self.label_multi_left(severity, *ls, None)?;
+                            }
+                            MultiLabel::Bottom(..) => self.inner_gutter_space()?,
+                        }
+                        multi_labels_iter.next();
+                    }
+                    Some((_, _, _)) | None => self.inner_gutter_space()?,
+                }
+            }
+
+            self.gutter_padding()?;
+            write!(self, "{: >width$}", "", width = column)?;
+            self.set_color(self.label_color(severity, label_style, hints))?;
+            write!(self, "{}", line)?;
+            self.reset()?;
+            writeln!(self)?;
+        }
+
+        Ok(())
+    }
+
+    /// A reference to a label in another file, underneath a snippet.
+    ///
+    /// ```text
+    /// │ defined here (see lib.rs:1:8)
+    /// ```
+    pub fn render_snippet_reference(
+        &mut self,
+        outer_padding: usize,
+        severity: Severity,
+        locus: &Locus,
+        message: &str,
+    ) -> Result<(), Error> {
+        self.outer_gutter(outer_padding)?;
+        self.border_left()?;
+        self.gutter_padding()?;
+        self.set_color(self.styles().label(severity, LabelStyle::Secondary))?;
+        if !message.is_empty() {
+            write!(self, "{} ", message)?;
+        }
+        write!(self, "(see ")?;
+        self.snippet_locus(locus)?;
+        write!(self, ")")?;
+        self.reset()?;
+        writeln!(self)?;
+
+        Ok(())
+    }
+
     /// An empty source line, for providing additional whitespace to source snippets.
     ///
     /// ```text
@@ -679,6 +1184,222 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
         Ok(())
     }
 
+    /// Top left border and locus, for narrow layouts.
+    ///
+    /// ```text
+    /// ┌─ test:2:9
+    /// ```
+    pub fn render_narrow_locus(&mut self, locus: &Locus) -> Result<(), Error> {
+        self.set_color(&self.styles().source_border)?;
+        write!(self, "{}", self.chars().snippet_start)?;
+        self.reset()?;
+
+        write!(self, " ")?;
+        self.snippet_locus(locus)?;
+        writeln!(self)?;
+
+        Ok(())
+    }
+
+    /// A standalone locus, on its own line.
+    ///
+    /// ```text
+    /// test:2:9
+    /// ```
+    pub fn render_locus(&mut self, locus: &Locus) -> Result<(), Error> {
+        self.snippet_locus(locus)?;
+        writeln!(self)?;
+
+        Ok(())
+    }
+
+    /// A line of a paragraph of text, without any border, using the style of
+    /// the given label if there is one.
+    ///
+    /// ```text
+    /// expected `Int` but found `String`
+    /// ```
+    pub fn render_paragraph(
+        &mut self,
+        label: Option<(Severity, LabelStyle, LabelHints)>,
+        text: &str,
+    ) -> Result<(), Error> {
+        match label {
+            Some((severity, label_style, hints)) => {
+                self.set_color(self.label_color(severity, label_style, hints))?;
+                write!(self, "{}", text)?;
+                self.reset()?;
+            }
+            None => write!(self, "{}", text)?,
+        }
+        writeln!(self)?;
+
+        Ok(())
+    }
+
+    /// A line of source code with a single label underneath, for narrow layouts.
+    ///
+    /// ```text
+    /// 2│ (+ test "")
+    ///  │         ^^
+    /// ```
+    pub fn render_narrow_source(
+        &mut self,
+        outer_padding: usize,
+        line_number: usize,
+        source: &str,
+        severity: Severity,
+        label: &SingleLabel<'_>,
+    ) -> Result<(), Error> {
+        let (label_style, range, _, hints) = label;
+        let source = source.trim_end_matches(['\n', '\r', '\0'].as_ref());
+
+        // Write source line
+        let width = self.line_number_width(outer_padding);
+        self.set_color(&self.styles().line_number)?;
+        let line_number = self.line_number_text(line_number);
+        self.aligned_line_number(&line_number, width)?;
+        self.reset()?;
+        self.border_left()?;
+        self.gutter_padding()?;
+        for (metrics, ch) in self.char_metrics(source.char_indices()) {
+            match ch {
+                '\t' if self.config.preserve_tabs_in_output => write!(self, "\t")?,
+                '\t' => (0..metrics.unicode_width).try_for_each(|_| write!(self, " "))?,
+                _ => write!(self, "{}", ch)?,
+            }
+        }
+        writeln!(self)?;
+
+        // Write the carets underneath
+        write!(self, "{: >width$}", "", width = width)?;
+        self.border_left()?;
+        self.gutter_padding()?;
+        let placeholder_metrics = Metrics {
+            byte_index: source.len(),
+            unicode_width: 1,
+        };
+        let caret = match label_style {
+            _ if hints.uncertain => self.chars().single_uncertain_caret,
+            LabelStyle::Primary => self.chars().single_primary_caret,
+            LabelStyle::Secondary => self.chars().single_secondary_caret,
+        };
+        let mut highlighted = false;
+        for (metrics, ch) in self
+            .char_metrics(source.char_indices())
+            .chain(std::iter::once((placeholder_metrics, '\0')))
+            .take_while(|(metrics, _)| metrics.byte_index < range.end)
+        {
+            let column_range = metrics.byte_index..(metrics.byte_index + ch.len_utf8());
+            let ch = if is_overlapping(range, &column_range) {
+                if !highlighted {
+                    self.set_color(self.label_color(severity, *label_style, *hints))?;
+                    highlighted = true;
+                }
+                caret
+            } else {
+                ' '
+            };
+            (0..metrics.unicode_width).try_for_each(|_| write!(self, "{}", ch))?;
+        }
+        if highlighted {
+            self.reset()?;
+        }
+        writeln!(self)?;
+
+        Ok(())
+    }
+
+    /// A line of a label message, for narrow layouts.
+    ///
+    /// ```text
+    ///  │ expected `Int` but found `String`
+    /// ```
+    pub fn render_narrow_message(
+        &mut self,
+        outer_padding: usize,
+        severity: Severity,
+        label_style: LabelStyle,
+        hints: LabelHints,
+        message: &str,
+    ) -> Result<(), Error> {
+        let width = self.line_number_width(outer_padding);
+        write!(self, "{: >width$}", "", width = width)?;
+        self.border_left()?;
+        self.gutter_padding()?;
+        self.set_color(self.label_color(severity, label_style, hints))?;
+        write!(self, "{}", message)?;
+        self.reset()?;
+        writeln!(self)?;
+
+        Ok(())
+    }
+
+    /// A row of two snippets rendered side by side, separated by a divider.
+    ///
+    /// Each side is clipped to the width of its column, and the left side is
+    /// padded to fill its column.
+    ///
+    /// ```text
+    /// 2 │ (+ test "")    │ 2 │ (+ test 1)
+    /// ```
+    pub fn render_side_by_side_row(
+        &mut self,
+        column_width: usize,
+        severity: Severity,
+        left: &[SidePart],
+        right: &[SidePart],
+    ) -> Result<(), Error> {
+        let left_width = self.side_by_side_column(column_width, severity, left)?;
+        write!(self, "{: >width$} ", "", width = column_width - left_width)?;
+        self.border_left()?;
+        if !right.is_empty() {
+            write!(self, " ")?;
+            self.side_by_side_column(column_width, severity, right)?;
+        }
+        writeln!(self)?;
+
+        Ok(())
+    }
+
+    /// Write one side of a side-by-side row, clipped to the column width.
+    /// Returns the width of the text that was written.
+    fn side_by_side_column(
+        &mut self,
+        column_width: usize,
+        severity: Severity,
+        parts: &[SidePart],
+    ) -> Result<usize, Error> {
+        use unicode_width::UnicodeWidthChar;
+
+        let mut width = 0;
+        for (side_style, text) in parts {
+            match side_style {
+                SideStyle::Source => {}
+                SideStyle::LineNumber => self.set_color(&self.styles().line_number)?,
+                SideStyle::Border => self.set_color(&self.styles().source_border)?,
+                SideStyle::Label(label_style) => {
+                    self.set_color(self.styles().label(severity, *label_style))?
+                }
+            }
+            for ch in text.chars() {
+                let ch_width = ch.width().unwrap_or(0);
+                if width + ch_width > column_width {
+                    self.reset()?;
+                    return Ok(width);
+                }
+                width += ch_width;
+                write!(self, "{}", ch)?;
+            }
+            match side_style {
+                SideStyle::Source => {}
+                SideStyle::LineNumber | SideStyle::Border | SideStyle::Label(_) => self.reset()?,
+            }
+        }
+
+        Ok(width)
+    }
+
     /// Adds tab-stop aware unicode-width computations to an iterator over
     /// character indices. Assumes that the character indices begin at the start
     /// of the line.
@@ -708,37 +1429,111 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
 
     /// Location focus.
     fn snippet_locus(&mut self, locus: &Locus) -> Result<(), Error> {
+        self.snippet_name(&locus.name)?;
         write!(
             self,
-            "{name}:{line_number}:{column_number}",
-            name = locus.name,
+            ":{line_number}:{column_number}",
             line_number = locus.location.line_number,
             column_number = locus.location.column_number,
         )?;
         Ok(())
     }
 
+    /// The name of a file, or [`Config::anonymous_file_name`] if it is empty.
+    fn snippet_name(&mut self, name: &str) -> Result<(), Error> {
+        let name = if name.is_empty() {
+            &self.config.anonymous_file_name
+        } else {
+            name
+        };
+        write!(self, "{}", name)?;
+        Ok(())
+    }
+
     /// The outer gutter of a source line.
     fn outer_gutter(&mut self, outer_padding: usize) -> Result<(), Error> {
-        write!(self, "{space: >width$} ", space = "", width = outer_padding)?;
+        write!(
+            self,
+            "{space: >width$}",
+            space = "",
+            width = self.line_number_width(outer_padding) + self.config.gutter_padding_left,
+        )?;
         Ok(())
     }
 
     /// The outer gutter of a source line, with line number.
     fn outer_gutter_number(
         &mut self,
-        line_number: usize,
+        line_number: Option<usize>,
         outer_padding: usize,
     ) -> Result<(), Error> {
+        let width = self.line_number_width(outer_padding);
         self.set_color(&self.styles().line_number)?;
+        let line_number = match line_number {
+            Some(line_number) => self.line_number_text(line_number),
+            None => self.chars().synthetic_line_marker.to_string(),
+        };
+        self.aligned_line_number(&line_number, width)?;
+        self.reset()?;
         write!(
             self,
-            "{line_number: >width$}",
-            line_number = line_number,
-            width = outer_padding,
+            "{space: >width$}",
+            space = "",
+            width = self.config.gutter_padding_left,
+        )?;
+        Ok(())
+    }
+
+    /// The text of a line number, with its digits grouped if
+    /// [`Config::group_line_number_digits`] is enabled.
+    fn line_number_text(&self, line_number: usize) -> String {
+        let digits = line_number.to_string();
+        if !self.config.group_line_number_digits {
+            return digits;
+        }
+
+        // The first group is shorter if the number of digits is not a
+        // multiple of three.
+        let mut end = match digits.len() % 3 {
+            0 => 3,
+            len => len,
+        };
+        let mut text = String::from(&digits[..end]);
+        while end < digits.len() {
+            text.push(self.chars().digit_group_separator);
+            text.push_str(&digits[end..end + 3]);
+            end += 3;
+        }
+        text
+    }
+
+    /// A line number, padded to the given width using [`Config::gutter_align`].
+    fn aligned_line_number(&mut self, line_number: &str, width: usize) -> Result<(), Error> {
+        match self.config.gutter_align {
+            GutterAlign::Left => write!(self, "{: <width$}", line_number, width = width)?,
+            GutterAlign::Right => write!(self, "{: >width$}", line_number, width = width)?,
+        }
+        Ok(())
+    }
+
+    /// The width of a line number with the given number of digits.
+    fn line_number_width(&self, num_digits: usize) -> usize {
+        if self.config.group_line_number_digits {
+            num_digits + num_digits.saturating_sub(1) / 3
+        } else {
+            num_digits
+        }
+    }
+
+    /// The padding between the left-hand border of a source line and its
+    /// source code.
+    fn gutter_padding(&mut self) -> Result<(), Error> {
+        write!(
+            self,
+            "{space: >width$}",
+            space = "",
+            width = self.config.gutter_padding_right,
         )?;
-        self.reset()?;
-        write!(self, " ")?;
         Ok(())
     }
 
@@ -769,15 +1564,15 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
     ) -> Result<(), Error> {
         for (metrics, ch) in self.char_metrics(char_indices) {
             let column_range = metrics.byte_index..(metrics.byte_index + ch.len_utf8());
-            let label_style = hanging_labels(single_labels, trailing_label)
-                .filter(|(_, range, _)| column_range.contains(&range.start))
-                .map(|(label_style, _, _)| *label_style)
-                .max_by_key(label_priority_key);
+            let label = hanging_labels(single_labels, trailing_label)
+                .filter(|(_, range, _, _)| column_range.contains(&range.start))
+                .map(|(label_style, _, _, hints)| (*label_style, *hints))
+                .max_by_key(|(label_style, _)| label_priority_key(label_style));
 
-            let mut spaces = match label_style {
+            let mut spaces = match label {
                 None => 0..metrics.unicode_width,
-                Some(label_style) => {
-                    self.set_color(self.styles().label(severity, label_style))?;
+                Some((label_style, hints)) => {
+                    self.set_color(self.label_color(severity, label_style, hints))?;
                     write!(self, "{}", self.chars().pointer_left)?;
                     self.reset()?;
                     1..metrics.unicode_width
@@ -866,14 +1661,25 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
     ) -> Result<(), Error> {
         self.set_color(self.styles().label(severity, label_style))?;
 
-        for (metrics, _) in self
+        let width = self
             .char_metrics(source.char_indices())
             .take_while(|(metrics, _)| metrics.byte_index < start + 1)
-        {
-            // FIXME: improve rendering of carets between character boundaries
-            (0..metrics.unicode_width)
-                .try_for_each(|_| write!(self, "{}", self.chars().multi_top))?;
-        }
+            .map(|(metrics, _)| {
+                // Round the start of the label down to the first column of the
+                // character it starts on, so that labels starting on a tab or a
+                // wide character point at its first column.
+                if metrics.byte_index < start {
+                    metrics.unicode_width
+                } else {
+                    std::cmp::min(metrics.unicode_width, 1)
+                }
+            })
+            .sum::<usize>();
+        // The underline also covers the padding before the source, except for
+        // the column that is taken by the top left of the label.
+        // FIXME: improve rendering of carets between character boundaries
+        let width = (width + self.config.gutter_padding_right).saturating_sub(1);
+        (0..width).try_for_each(|_| write!(self, "{}", self.chars().multi_top))?;
 
         let caret_start = match label_style {
             LabelStyle::Primary => self.config.chars.multi_primary_caret_start,
@@ -900,14 +1706,16 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
     ) -> Result<(), Error> {
         self.set_color(self.styles().label(severity, label_style))?;
 
-        for (metrics, _) in self
+        let width = self
             .char_metrics(source.char_indices())
             .take_while(|(metrics, _)| metrics.byte_index < start)
-        {
-            // FIXME: improve rendering of carets between character boundaries
-            (0..metrics.unicode_width)
-                .try_for_each(|_| write!(self, "{}", self.chars().multi_bottom))?;
-        }
+            .map(|(metrics, _)| metrics.unicode_width)
+            .sum::<usize>();
+        // The underline also covers the padding before the source, except for
+        // the column that is taken by the bottom left of the label.
+        // FIXME: improve rendering of carets between character boundaries
+        let width = (width + self.config.gutter_padding_right).saturating_sub(1);
+        (0..width).try_for_each(|_| write!(self, "{}", self.chars().multi_bottom))?;
 
         let caret_end = match label_style {
             LabelStyle::Primary => self.config.chars.multi_primary_caret_start,
@@ -915,7 +1723,7 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
         };
         write!(self, "{}", caret_end)?;
         if !message.is_empty() {
-            write!(self, " {}", message)?;
+            write!(self, " {}{}", self.message_bullet(label_style), message)?;
         }
         self.reset()?;
         writeln!(self)?;
@@ -977,31 +1785,36 @@ impl<'writer, 'config> Renderer<'writer, 'config> {
     }
 }
 
-impl<'writer, 'config> Write for Renderer<'writer, 'config> {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.writer.write(buf)
+impl<'writer, 'config> Renderer<'writer, 'config> {
+    fn set_color(&mut self, spec: &ColorSpec) -> io::Result<()> {
+        if self.dimmed {
+            self.writer.set_style(spec.clone().set_dimmed(true))
+        } else {
+            self.writer.set_style(spec)
+        }
     }
 
-    fn flush(&mut self) -> io::Result<()> {
-        self.writer.flush()
+    fn reset(&mut self) -> io::Result<()> {
+        if self.dimmed {
+            self.writer.set_style(ColorSpec::new().set_dimmed(true))
+        } else {
+            self.writer.reset()
+        }
     }
 }
 
-impl<'writer, 'config> WriteColor for Renderer<'writer, 'config> {
-    fn supports_color(&self) -> bool {
-        self.writer.supports_color()
-    }
-
-    fn set_color(&mut self, spec: &ColorSpec) -> io::Result<()> {
-        self.writer.set_color(spec)
-    }
-
-    fn reset(&mut self) -> io::Result<()> {
-        self.writer.reset()
+impl<'writer, 'config> Write for Renderer<'writer, 'config> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // The renderer only writes formatted strings, which are written in one
+        // piece, so this only fails if the buffer was split up by the caller.
+        let text = std::str::from_utf8(buf)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        self.writer.write_str(text)?;
+        Ok(buf.len())
     }
 
-    fn is_synchronous(&self) -> bool {
-        self.writer.is_synchronous()
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
     }
 }
 
@@ -1018,7 +1831,7 @@ fn is_overlapping(range0: &Range<usize>, range1: &Range<usize>) -> bool {
 }
 
 /// For prioritizing primary labels over secondary labels when rendering carets.
-fn label_priority_key(label_style: &LabelStyle) -> u8 {
+pub fn label_priority_key(label_style: &LabelStyle) -> u8 {
     match label_style {
         LabelStyle::Secondary => 0,
         LabelStyle::Primary => 1,
@@ -1034,7 +1847,10 @@ fn hanging_labels<'labels, 'diagnostic>(
     single_labels
         .iter()
         .enumerate()
-        .filter(|(_, (_, _, message))| !message.is_empty())
-        .filter(move |(i, _)| trailing_label.map_or(true, |(j, _)| *i != j))
+        .filter(|(_, (_, _, message, _))| !message.is_empty())
+        .filter(move |(i, _)| match trailing_label {
+            Some((j, _)) => *i != j,
+            None => true,
+        })
         .map(|(_, label)| label)
 }
@@ -0,0 +1,257 @@
+//! Support for rendering labels whose locations were resolved ahead of time.
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::diagnostic::{Diagnostic, Label};
+use crate::files::{column_index, Error, Files};
+
+/// The location of a label that has already been resolved by the caller.
+///
+/// This is used as the file id of the labels passed to [`emit_resolved`], so
+/// that the renderer does not need to look up line indices or line ranges via
+/// a [`Files`] database. The range of each label is interpreted as a byte range
+/// relative to the start of [`line_text`], and is clamped to the end of the
+/// line.
+///
+/// [`emit_resolved`]: crate::term::emit_resolved
+/// [`Files`]: crate::files::Files
+/// [`line_text`]: ResolvedLocation::line_text
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedLocation<'a> {
+    /// The user-facing name of the file.
+    pub name: &'a str,
+    /// The user-facing line number of the labelled line.
+    pub line_number: usize,
+    /// The user-facing column number at the start of the label.
+    pub column_number: usize,
+    /// The source text of the labelled line.
+    pub line_text: &'a str,
+}
+
+/// A line in a [`ResolvedFile`], positioned in the file's virtual source.
+struct ResolvedLine {
+    number: usize,
+    range: Range<usize>,
+}
+
+/// A file made up of only the lines that were labelled.
+///
+/// Labelled lines are stored in order of their line numbers. If two labelled
+/// lines are separated by a single line in the original file, an empty
+/// placeholder line is inserted between them, which the renderer displays in
+/// place of that line. If they are further apart, two empty placeholder lines
+/// are inserted so that the renderer displays a source break.
+///
+/// The source of a file with a single labelled line borrows the text of that
+/// line. Only files with several labelled lines need to join their lines.
+struct ResolvedFile<'a> {
+    name: &'a str,
+    source: Cow<'a, str>,
+    lines: Vec<ResolvedLine>,
+    /// The column numbers of the starts of labels, sorted by byte index in the
+    /// virtual source.
+    columns: Vec<(usize, usize)>,
+}
+
+/// An internal file database built from [`ResolvedLocation`]s.
+pub struct ResolvedFiles<'a> {
+    /// The files, sorted by name.
+    files: Vec<ResolvedFile<'a>>,
+}
+
+impl<'a> ResolvedFiles<'a> {
+    /// Build a file database from the labels of the diagnostic, returning an
+    /// equivalent diagnostic whose labels refer to the new database.
+    pub fn new(
+        diagnostic: &Diagnostic<ResolvedLocation<'a>>,
+    ) -> (ResolvedFiles<'a>, Diagnostic<usize>) {
+        // Collect the labelled lines of each file, in line order.
+        let mut locations = diagnostic
+            .labels
+            .iter()
+            .chain(diagnostic.steps.iter().map(|(label, _)| label))
+            .map(|label| &label.file_id)
+            .collect::<Vec<_>>();
+        locations.sort_by_key(|location| (location.name, location.line_number));
+        locations.dedup_by_key(|location| (location.name, location.line_number));
+
+        let mut files = Vec::<ResolvedFile<'_>>::new();
+        let mut remaining_locations = &locations[..];
+        while let Some(first_location) = remaining_locations.first() {
+            let num_locations = remaining_locations
+                .iter()
+                .take_while(|location| location.name == first_location.name)
+                .count();
+            let (file_locations, rest) = remaining_locations.split_at(num_locations);
+            remaining_locations = rest;
+
+            let mut lines = Vec::with_capacity(file_locations.len());
+            let source = match file_locations {
+                [location] => {
+                    lines.push(ResolvedLine {
+                        number: location.line_number,
+                        range: 0..location.line_text.len(),
+                    });
+                    Cow::Borrowed(location.line_text)
+                }
+                _ => {
+                    let mut source = String::new();
+                    let mut last_line_number = None;
+                    for location in file_locations {
+                        // A single unlabelled line is rendered between two
+                        // labelled lines, like `emit` does, but its text is
+                        // not known. Larger gaps are rendered as a source
+                        // break.
+                        let num_placeholders = match last_line_number {
+                            Some(number) if number + 2 == location.line_number => 1,
+                            Some(number) if number + 2 < location.line_number => 2,
+                            Some(_) | None => 0,
+                        };
+                        for _ in 0..num_placeholders {
+                            let start = source.len();
+                            source.push('\n');
+                            lines.push(ResolvedLine {
+                                number: last_line_number.map_or(0, |number| number + 1),
+                                range: start..source.len(),
+                            });
+                        }
+
+                        let start = source.len();
+                        source.push_str(location.line_text);
+                        if !location.line_text.ends_with('\n') {
+                            source.push('\n');
+                        }
+                        lines.push(ResolvedLine {
+                            number: location.line_number,
+                            range: start..source.len(),
+                        });
+                        last_line_number = Some(location.line_number);
+                    }
+                    Cow::Owned(source)
+                }
+            };
+            files.push(ResolvedFile {
+                name: first_location.name,
+                source,
+                lines,
+                columns: Vec::new(),
+            });
+        }
+
+        // Convert the line-relative label ranges into ranges in the virtual sources.
+        let mut resolve_label = |label: &Label<ResolvedLocation<'a>>| {
+            let location = &label.file_id;
+            let file_id = files
+                .binary_search_by_key(&location.name, |file| file.name)
+                .expect("file was inserted above");
+            let file = &mut files[file_id];
+            let line_index = file
+                .lines
+                .binary_search_by_key(&location.line_number, |line| line.number)
+                .expect("line was inserted above");
+            let line_start = file.lines[line_index].range.start;
+
+            let line_len = location.line_text.trim_end_matches('\n').len();
+            let start = line_start + usize::min(label.range.start, line_len);
+            let end = line_start + usize::min(label.range.end, line_len);
+            file.columns.push((start, location.column_number));
+
+            Label {
+                style: label.style,
+                file_id,
+                range: start..usize::max(start, end),
+                message: label.message.clone(),
+                detail: label.detail.clone(),
+                display_column: label.display_column,
+                certainty: label.certainty,
+                tag: label.tag,
+                severity_hint: label.severity_hint,
+                synthetic_line: label.synthetic_line.clone(),
+                message_only: label.message_only,
+            }
+        };
+        let labels = diagnostic.labels.iter().map(&mut resolve_label).collect();
+        let steps = diagnostic
+            .steps
+            .iter()
+            .map(|(label, description)| (resolve_label(label), description.clone()))
+            .collect();
+        for file in &mut files {
+            file.columns.sort_by_key(|(start, _)| *start);
+        }
+
+        let diagnostic = Diagnostic {
+            severity: diagnostic.severity,
+            code: diagnostic.code.clone(),
+            message: diagnostic.message.clone(),
+            labels,
+            notes: diagnostic.notes.clone(),
+            steps,
+            // Suggestions are not rendered, so they do not need to be resolved.
+            suggestions: Vec::new(),
+        };
+
+        (ResolvedFiles { files }, diagnostic)
+    }
+
+    fn get(&self, file_id: usize) -> Result<&ResolvedFile<'a>, Error> {
+        self.files.get(file_id).ok_or(Error::FileMissing)
+    }
+}
+
+impl<'a> Files<'a> for ResolvedFiles<'a> {
+    type FileId = usize;
+    type Name = &'a str;
+    type Source = &'a str;
+
+    fn name(&'a self, file_id: usize) -> Result<&'a str, Error> {
+        Ok(self.get(file_id)?.name)
+    }
+
+    fn source(&'a self, file_id: usize) -> Result<&'a str, Error> {
+        Ok(&self.get(file_id)?.source)
+    }
+
+    fn line_index(&'a self, file_id: usize, byte_index: usize) -> Result<usize, Error> {
+        let lines = &self.get(file_id)?.lines;
+        Ok(lines
+            .binary_search_by_key(&byte_index, |line| line.range.start)
+            .unwrap_or_else(|next_line| next_line.saturating_sub(1)))
+    }
+
+    fn line_number(&'a self, file_id: usize, line_index: usize) -> Result<usize, Error> {
+        let file = self.get(file_id)?;
+        let line = file.lines.get(line_index).ok_or(Error::LineTooLarge {
+            given: line_index,
+            max: file.lines.len().saturating_sub(1),
+        })?;
+        Ok(line.number)
+    }
+
+    fn column_number(
+        &'a self,
+        file_id: usize,
+        line_index: usize,
+        byte_index: usize,
+    ) -> Result<usize, Error> {
+        let file = self.get(file_id)?;
+        let line_range = self.line_range(file_id, line_index)?;
+        match file
+            .columns
+            .binary_search_by_key(&byte_index, |(start, _)| *start)
+        {
+            Ok(index) => Ok(file.columns[index].1),
+            Err(_) => Ok(column_index(&file.source, line_range, byte_index) + 1),
+        }
+    }
+
+    fn line_range(&'a self, file_id: usize, line_index: usize) -> Result<Range<usize>, Error> {
+        let file = self.get(file_id)?;
+        let line = file.lines.get(line_index).ok_or(Error::LineTooLarge {
+            given: line_index,
+            max: file.lines.len().saturating_sub(1),
+        })?;
+        Ok(line.range.clone())
+    }
+}
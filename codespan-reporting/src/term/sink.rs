@@ -0,0 +1,83 @@
+//! Support for rendering diagnostics to styling back-ends other than termcolor.
+
+use std::io;
+use termcolor::{ColorSpec, WriteColor};
+
+/// A destination for styled text, which the renderer writes diagnostics to.
+///
+/// This can be implemented for styling back-ends that do not implement
+/// [`WriteColor`], such as a graphical text renderer. Writers that do can be
+/// used with [`TermcolorSink`].
+pub trait StyledSink {
+    /// Write some text, using the current style.
+    fn write_str(&mut self, text: &str) -> io::Result<()>;
+
+    /// Set the style of the text that is written after this.
+    ///
+    /// The style is a termcolor [`ColorSpec`], even for sinks that do not use
+    /// termcolor. Every style that the renderer sets comes from [`Styles`],
+    /// which is made of `ColorSpec`s, or is dimmed as described there, so this
+    /// passes them through as they were configured rather than converting them
+    /// to a second style type with the same colors and attributes. Sinks for other back-ends can read the
+    /// colors and attributes with the getters of `ColorSpec`, and termcolor is
+    /// re-exported as [`term::termcolor`] so that they can do so without
+    /// depending on it directly.
+    ///
+    /// [`Styles`]: crate::term::Styles
+    /// [`term::termcolor`]: crate::term::termcolor
+    fn set_style(&mut self, spec: &ColorSpec) -> io::Result<()>;
+
+    /// Reset the style to the default style.
+    fn reset(&mut self) -> io::Result<()>;
+}
+
+impl<S: StyledSink + ?Sized> StyledSink for &mut S {
+    fn write_str(&mut self, text: &str) -> io::Result<()> {
+        (**self).write_str(text)
+    }
+
+    fn set_style(&mut self, spec: &ColorSpec) -> io::Result<()> {
+        (**self).set_style(spec)
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        (**self).reset()
+    }
+}
+
+/// A [`StyledSink`] that writes to a termcolor [`WriteColor`].
+#[derive(Debug)]
+pub struct TermcolorSink<W> {
+    writer: W,
+}
+
+impl<W: WriteColor> TermcolorSink<W> {
+    /// Create a sink that writes to the given writer.
+    pub fn new(writer: W) -> TermcolorSink<W> {
+        TermcolorSink { writer }
+    }
+
+    /// Get a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Consume the sink, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: WriteColor> StyledSink for TermcolorSink<W> {
+    fn write_str(&mut self, text: &str) -> io::Result<()> {
+        self.writer.write_all(text.as_bytes())
+    }
+
+    fn set_style(&mut self, spec: &ColorSpec) -> io::Result<()> {
+        self.writer.set_color(spec)
+    }
+
+    fn reset(&mut self) -> io::Result<()> {
+        self.writer.reset()
+    }
+}
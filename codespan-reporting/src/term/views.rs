@@ -1,9 +1,13 @@
+use std::borrow::Cow;
 use std::ops::Range;
 
-use crate::diagnostic::{Diagnostic, LabelStyle};
+use crate::diagnostic::{Diagnostic, Label, LabelStyle, Severity};
 use crate::files::{Error, Files, Location};
-use crate::term::renderer::{Locus, MultiLabel, Renderer, SingleLabel};
-use crate::term::Config;
+use crate::term::renderer::{
+    label_priority_key, severity_name, LabelHints, Locus, MultiLabel, Renderer, SidePart,
+    SideStyle, SingleLabel,
+};
+use crate::term::{Config, GutterAlign};
 
 /// Calculate the number of decimal digits in `n`.
 // TODO: simplify after https://github.com/rust-lang/rust/issues/70887 resolves
@@ -13,10 +17,220 @@ fn count_digits(n: usize) -> usize {
     (n.saturating_add(1) as f64).log10().ceil() as usize
 }
 
+/// The number of bytes of leading whitespace that the given lines have in
+/// common. Lines that only contain whitespace are ignored.
+fn common_indent<'a>(lines: impl IntoIterator<Item = &'a str>) -> usize {
+    let mut common: Option<&str> = None;
+    for line in lines {
+        let line = line.trim_end_matches(['\n', '\r', '\0'].as_ref());
+        let text = line.trim_start();
+        if text.is_empty() {
+            continue;
+        }
+        let indent = &line[..line.len() - text.len()];
+        common = Some(match common {
+            None => indent,
+            Some(common) => {
+                let len = common
+                    .char_indices()
+                    .zip(indent.chars())
+                    .find(|((_, common_ch), ch)| common_ch != ch)
+                    .map_or(usize::min(common.len(), indent.len()), |((index, _), _)| {
+                        index
+                    });
+                &common[..len]
+            }
+        });
+    }
+    common.map_or(0, str::len)
+}
+
+type MultiLabels<'diagnostic> = [(usize, LabelStyle, MultiLabel<'diagnostic>)];
+
+/// Remove up to `dedent` bytes of leading whitespace from a line of source,
+/// shifting the positions of its labels to match.
+fn dedent_line<'source, 'labels, 'diagnostic>(
+    source: &'source str,
+    dedent: usize,
+    single_labels: &'labels [SingleLabel<'diagnostic>],
+    multi_labels: &'labels MultiLabels<'diagnostic>,
+) -> (
+    &'source str,
+    Cow<'labels, [SingleLabel<'diagnostic>]>,
+    Cow<'labels, MultiLabels<'diagnostic>>,
+) {
+    let dedent = usize::min(dedent, source.len() - source.trim_start().len());
+    if dedent == 0 {
+        return (
+            source,
+            Cow::Borrowed(single_labels),
+            Cow::Borrowed(multi_labels),
+        );
+    }
+
+    let single_labels = single_labels
+        .iter()
+        .map(|(label_style, range, message, hints)| {
+            let start = range.start.saturating_sub(dedent);
+            let end = usize::max(range.end.saturating_sub(dedent), start + 1);
+            (*label_style, start..end, *message, *hints)
+        })
+        .collect();
+    let multi_labels = multi_labels
+        .iter()
+        .map(|(label_index, label_style, label)| {
+            let label = match label {
+                MultiLabel::Top(start) => MultiLabel::Top(start.saturating_sub(dedent)),
+                MultiLabel::Left => MultiLabel::Left,
+                MultiLabel::Bottom(end, message) => {
+                    MultiLabel::Bottom(end.saturating_sub(dedent), message)
+                }
+            };
+            (*label_index, *label_style, label)
+        })
+        .collect();
+
+    (
+        &source[dedent..],
+        Cow::Owned(single_labels),
+        Cow::Owned(multi_labels),
+    )
+}
+
+/// Whether the certainty of a label is below the threshold of the config.
+pub fn is_uncertain<FileId>(config: &Config, label: &Label<FileId>) -> bool {
+    match label.certainty {
+        Some(certainty) => certainty < config.certainty_threshold,
+        None => false,
+    }
+}
+
+/// The hints about how a single-line label should be rendered.
+fn label_hints<FileId>(config: &Config, label: &Label<FileId>) -> LabelHints {
+    LabelHints {
+        uncertain: is_uncertain(config, label),
+        severity: label.severity_hint,
+        message_only: label.message_only,
+    }
+}
+
+/// The location of the start of a label, using its display column if it has one.
+pub fn label_location<'files, FileId>(
+    files: &'files impl Files<'files, FileId = FileId>,
+    label: &Label<FileId>,
+) -> Result<Location, Error>
+where
+    FileId: 'files + Copy,
+{
+    let location = files.location(label.file_id, label.range.start)?;
+    Ok(match label.display_column {
+        Some(column_number) => Location {
+            column_number: usize::max(column_number, 1),
+            ..location
+        },
+        None => location,
+    })
+}
+
+/// The number of bytes that a line of source is clipped to by
+/// [`Config::max_line_scan`], or `None` if the line is rendered whole.
+fn clip_len(config: &Config, line: &str) -> Option<usize> {
+    let line = line.trim_end_matches(['\n', '\r', '\0'].as_ref());
+    match config.max_line_scan {
+        Some(max_line_scan) if line.len() > max_line_scan => {
+            let mut end = max_line_scan;
+            while !line.is_char_boundary(end) {
+                end -= 1;
+            }
+            Some(end)
+        }
+        Some(_) | None => None,
+    }
+}
+
+/// Get the range of a line of source, clipped to [`Config::max_line_scan`]
+/// bytes, along with whether it was clipped.
+///
+/// This avoids scanning the whole of an extremely long line in order to render
+/// a label on it. The range of a clipped line excludes its line terminator, and
+/// its text is rendered with [`with_clip_marker`].
+fn clipped_line<'files, FileId>(
+    files: &'files impl Files<'files, FileId = FileId>,
+    config: &Config,
+    file_id: FileId,
+    line_index: usize,
+) -> Result<(Range<usize>, bool), Error>
+where
+    FileId: 'files + Copy,
+{
+    let line_range = files.line_range(file_id, line_index)?;
+    if config.max_line_scan.is_none() {
+        return Ok((line_range, false));
+    }
+    let source = files.source(file_id)?;
+    Ok(
+        match clip_len(config, &source.as_ref()[line_range.clone()]) {
+            Some(len) => (line_range.start..line_range.start + len, true),
+            None => (line_range, false),
+        },
+    )
+}
+
+/// Append a [`Chars::source_clip`] marker to the text of a clipped line.
+///
+/// Labels that are past the end of a clipped line should be clamped to the
+/// start of the marker with [`clamp_to_clip`].
+///
+/// [`Chars::source_clip`]: crate::term::Chars::source_clip
+fn with_clip_marker<'a>(config: &Config, line: &'a str, clipped: bool) -> Cow<'a, str> {
+    if clipped {
+        Cow::Owned(format!("{}{}", line, config.chars.source_clip))
+    } else {
+        Cow::Borrowed(line)
+    }
+}
+
+/// Clamp a byte offset into a line to the length of the line, if the line was
+/// clipped, so that labels past the clipped extent are rendered at the marker.
+fn clamp_to_clip(offset: usize, line_len: usize, clipped: bool) -> usize {
+    if clipped {
+        usize::min(offset, line_len)
+    } else {
+        offset
+    }
+}
+
+/// Convert a one-based display column on a line of source into the byte range
+/// of the character at that column. If the column is past the end of the line,
+/// the range starts past the end of the line, and the number of spaces that the
+/// line needs to be padded with to reach the column is returned as well.
+fn display_column_range(line: &str, column: usize, tab_width: usize) -> (Range<usize>, usize) {
+    use unicode_width::UnicodeWidthChar;
+
+    let column = column.saturating_sub(1);
+    let mut line_column = 0;
+    for (byte_index, ch) in line.char_indices() {
+        let width = match (ch, tab_width) {
+            ('\t', 0) => 0, // Guard divide-by-zero
+            ('\t', _) => tab_width - (line_column % tab_width),
+            (ch, _) => ch.width().unwrap_or(0),
+        };
+        if column < line_column + width {
+            return (byte_index..byte_index + ch.len_utf8(), 0);
+        }
+        line_column += width;
+    }
+
+    let padding = column - line_column;
+    (line.len() + padding..line.len() + padding + 1, padding)
+}
+
 /// Output a richly formatted diagnostic, with source code previews.
 pub struct RichDiagnostic<'diagnostic, 'config, FileId> {
     diagnostic: &'diagnostic Diagnostic<FileId>,
     config: &'config Config,
+    /// The file and range of the label to focus on, if any.
+    focus: Option<(FileId, Range<usize>)>,
 }
 
 impl<'diagnostic, 'config, FileId> RichDiagnostic<'diagnostic, 'config, FileId>
@@ -27,7 +241,20 @@ where
         diagnostic: &'diagnostic Diagnostic<FileId>,
         config: &'config Config,
     ) -> RichDiagnostic<'diagnostic, 'config, FileId> {
-        RichDiagnostic { diagnostic, config }
+        RichDiagnostic {
+            diagnostic,
+            config,
+            focus: None,
+        }
+    }
+
+    /// Dim everything except for the lines that overlap the given label range.
+    pub fn with_focus(
+        mut self,
+        focus: Option<(FileId, Range<usize>)>,
+    ) -> RichDiagnostic<'diagnostic, 'config, FileId> {
+        self.focus = focus;
+        self
     }
 
     pub fn render<'files>(
@@ -44,9 +271,12 @@ where
             file_id: FileId,
             start: usize,
             name: String,
-            location: Location,
+            /// The location of the snippet, which is `None` if the file only
+            /// has labels on synthetic lines.
+            location: Option<Location>,
             num_multi_labels: usize,
             lines: BTreeMap<usize, Line<'diagnostic>>,
+            synthetic_lines: Vec<SyntheticLine<'diagnostic>>,
             max_label_style: LabelStyle,
         }
 
@@ -54,14 +284,18 @@ where
             fn get_or_insert_line(
                 &mut self,
                 line_index: usize,
-                line_range: Range<usize>,
+                (line_range, clipped): (Range<usize>, bool),
                 line_number: usize,
             ) -> &mut Line<'diagnostic> {
                 self.lines.entry(line_index).or_insert_with(|| Line {
                     range: line_range,
+                    clipped,
                     number: line_number,
                     single_labels: vec![],
                     multi_labels: vec![],
+                    details: vec![],
+                    padding: 0,
+                    newline_marker: false,
                     // This has to be false by default so we know if it must be rendered by another condition already.
                     must_render: false,
                 })
@@ -71,26 +305,188 @@ where
         struct Line<'diagnostic> {
             number: usize,
             range: std::ops::Range<usize>,
+            /// Whether the line was clipped by [`Config::max_line_scan`].
+            clipped: bool,
             // TODO: How do we reuse these allocations?
             single_labels: Vec<SingleLabel<'diagnostic>>,
             multi_labels: Vec<(usize, LabelStyle, MultiLabel<'diagnostic>)>,
+            /// The details of the labels that end on this line, along with
+            /// the byte index that they should be aligned with.
+            details: Vec<(LabelStyle, LabelHints, usize, &'diagnostic str)>,
+            /// The number of spaces to pad the line with, so that labels
+            /// pointing past its end can be rendered.
+            padding: usize,
+            /// Whether a single-line label covers the line terminator, which is
+            /// then rendered as a [`Chars::newline_marker`].
+            ///
+            /// [`Chars::newline_marker`]: crate::term::Chars::newline_marker
+            newline_marker: bool,
             must_render: bool,
         }
 
+        /// A line of source text given by [`Label::synthetic_line`], which is
+        /// rendered after the lines of the file.
+        struct SyntheticLine<'diagnostic> {
+            source: &'diagnostic str,
+            /// The length that the line is clipped to by [`Config::max_line_scan`].
+            clip_len: Option<usize>,
+            single_labels: Vec<SingleLabel<'diagnostic>>,
+            details: Vec<(LabelStyle, LabelHints, usize, &'diagnostic str)>,
+        }
+
         // TODO: Make this data structure external, to allow for allocation reuse
         let mut labeled_files = Vec::<LabeledFile<'_, _>>::new();
         // Keep track of the outer padding to use when rendering the
         // snippets of source code.
         let mut outer_padding = 0;
+        // Secondary labels in files without primary labels, which are
+        // rendered as references under the primary snippet if requested.
+        let mut cross_file_refs = Vec::new();
+        let primary_file_ids = self
+            .diagnostic
+            .labels
+            .iter()
+            .filter(|label| label.style == LabelStyle::Primary)
+            .map(|label| label.file_id)
+            .collect::<Vec<_>>();
 
         // Group labels by file
         for label in &self.diagnostic.labels {
+            if let Some(source) = &label.synthetic_line {
+                // Labels on synthetic lines are clamped to the end of the line,
+                // or to the clip marker if the line is clipped.
+                let clip_len = clip_len(self.config, source);
+                let line_len = clip_len
+                    .unwrap_or_else(|| source.trim_end_matches(['\n', '\r', '\0'].as_ref()).len());
+                let label_start = usize::min(label.range.start, line_len);
+                let label_end = usize::min(label.range.end, line_len);
+                let label_end = usize::max(label_end, label_start + 1);
+                outer_padding = std::cmp::max(outer_padding, 1);
+
+                let labeled_file = match labeled_files
+                    .iter_mut()
+                    .position(|labeled_file| label.file_id == labeled_file.file_id)
+                {
+                    Some(index) => &mut labeled_files[index],
+                    None => {
+                        labeled_files.push(LabeledFile {
+                            file_id: label.file_id,
+                            start: 0,
+                            name: files.name(label.file_id)?.to_string(),
+                            location: None,
+                            num_multi_labels: 0,
+                            lines: BTreeMap::new(),
+                            synthetic_lines: Vec::new(),
+                            max_label_style: label.style,
+                        });
+                        labeled_files
+                            .last_mut()
+                            .expect("just pushed an element that disappeared")
+                    }
+                };
+                if labeled_file.max_label_style > label.style {
+                    labeled_file.max_label_style = label.style;
+                }
+
+                let line = match labeled_file
+                    .synthetic_lines
+                    .iter()
+                    .position(|line| line.source == source.as_str())
+                {
+                    Some(index) => &mut labeled_file.synthetic_lines[index],
+                    None => {
+                        labeled_file.synthetic_lines.push(SyntheticLine {
+                            source,
+                            clip_len,
+                            single_labels: Vec::new(),
+                            details: Vec::new(),
+                        });
+                        labeled_file
+                            .synthetic_lines
+                            .last_mut()
+                            .expect("just pushed an element that disappeared")
+                    }
+                };
+                line.single_labels.push((
+                    label.style,
+                    label_start..label_end,
+                    &label.message,
+                    label_hints(self.config, label),
+                ));
+                line.single_labels
+                    .sort_by_key(|(style, range, message, _)| {
+                        (range.start, range.end, *message, label_priority_key(style))
+                    });
+                if let Some(detail) = &label.detail {
+                    line.details.push((
+                        label.style,
+                        label_hints(self.config, label),
+                        label_start,
+                        detail,
+                    ));
+                }
+                continue;
+            }
+
+            if self.config.inline_cross_file_refs
+                && label.style == LabelStyle::Secondary
+                && !primary_file_ids.is_empty()
+                && !primary_file_ids.contains(&label.file_id)
+            {
+                let locus = Locus {
+                    name: files.name(label.file_id)?.to_string(),
+                    location: label_location(files, label)?,
+                };
+                cross_file_refs.push((locus, label.message.as_str()));
+                continue;
+            }
+
             let start_line_index = files.line_index(label.file_id, label.range.start)?;
             let start_line_number = files.line_number(label.file_id, start_line_index)?;
-            let start_line_range = files.line_range(label.file_id, start_line_index)?;
-            let end_line_index = files.line_index(label.file_id, label.range.end)?;
-            let end_line_number = files.line_number(label.file_id, end_line_index)?;
-            let end_line_range = files.line_range(label.file_id, end_line_index)?;
+            let (start_line_range, start_clipped) =
+                clipped_line(files, self.config, label.file_id, start_line_index)?;
+
+            // Labels that point at a display column are converted to a range
+            // on the line containing their start. If the column is past the
+            // end of the line, the line is padded with spaces to reach it.
+            let (range, padding) = match label.display_column {
+                Some(column) => {
+                    let source = files.source(label.file_id)?;
+                    let line = source.as_ref()[start_line_range.clone()]
+                        .trim_end_matches(['\n', '\r', '\0'].as_ref());
+                    let (range, padding) =
+                        display_column_range(line, column, self.config.tab_width);
+                    let range = (start_line_range.start + range.start)
+                        ..(start_line_range.start + range.end);
+                    (range, padding)
+                }
+                None => (label.range.clone(), 0),
+            };
+            let (end_line_index, end_line_number, (end_line_range, end_clipped)) =
+                match label.display_column {
+                    Some(_) => (
+                        start_line_index,
+                        start_line_number,
+                        (start_line_range.clone(), start_clipped),
+                    ),
+                    None => {
+                        let mut end_line_index = files.line_index(label.file_id, range.end)?;
+                        // A label that ends with the line terminator of its first
+                        // line is rendered on that line, rather than as a
+                        // multi-line label that ends at the start of the next one.
+                        if end_line_index != start_line_index
+                            && range.start < range.end
+                            && files.line_index(label.file_id, range.end - 1)? == start_line_index
+                        {
+                            end_line_index = start_line_index;
+                        }
+                        (
+                            end_line_index,
+                            files.line_number(label.file_id, end_line_index)?,
+                            clipped_line(files, self.config, label.file_id, end_line_index)?,
+                        )
+                    }
+                };
 
             outer_padding = std::cmp::max(outer_padding, count_digits(start_line_number));
             outer_padding = std::cmp::max(outer_padding, count_digits(end_line_number));
@@ -104,14 +500,17 @@ where
             {
                 Some(labeled_file) => {
                     // another diagnostic also referenced this file
-                    if labeled_file.max_label_style > label.style
+                    if labeled_file.location.is_none()
+                        || labeled_file.max_label_style > label.style
                         || (labeled_file.max_label_style == label.style
-                            && labeled_file.start > label.range.start)
+                            && labeled_file.start > range.start)
                     {
                         // this label has a higher style or has the same style but starts earlier
-                        labeled_file.start = label.range.start;
-                        labeled_file.location = files.location(label.file_id, label.range.start)?;
-                        labeled_file.max_label_style = label.style;
+                        labeled_file.start = range.start;
+                        labeled_file.location = Some(label_location(files, label)?);
+                        if labeled_file.max_label_style > label.style {
+                            labeled_file.max_label_style = label.style;
+                        }
                     }
                     labeled_file
                 }
@@ -119,11 +518,12 @@ where
                     // no other diagnostic referenced this file yet
                     labeled_files.push(LabeledFile {
                         file_id: label.file_id,
-                        start: label.range.start,
+                        start: range.start,
                         name: files.name(label.file_id)?.to_string(),
-                        location: files.location(label.file_id, label.range.start)?,
+                        location: Some(label_location(files, label)?),
                         num_multi_labels: 0,
                         lines: BTreeMap::new(),
+                        synthetic_lines: Vec::new(),
                         max_label_style: label.style,
                     });
                     // this unwrap should never fail because we just pushed an element
@@ -145,9 +545,9 @@ where
                     break;
                 };
 
-                if let Ok(range) = files.line_range(label.file_id, index) {
+                if let Ok(line) = clipped_line(files, self.config, label.file_id, index) {
                     let line =
-                        labeled_file.get_or_insert_line(index, range, start_line_number - offset);
+                        labeled_file.get_or_insert_line(index, line, start_line_number - offset);
                     line.must_render = true;
                 } else {
                     break;
@@ -161,9 +561,9 @@ where
                     .checked_add(offset)
                     .expect("line index too big");
 
-                if let Ok(range) = files.line_range(label.file_id, index) {
+                if let Ok(line) = clipped_line(files, self.config, label.file_id, index) {
                     let line =
-                        labeled_file.get_or_insert_line(index, range, end_line_number + offset);
+                        labeled_file.get_or_insert_line(index, line, end_line_number + offset);
                     line.must_render = true;
                 } else {
                     break;
@@ -177,33 +577,91 @@ where
                 // 2 │ (+ test "")
                 //   │         ^^ expected `Int` but found `String`
                 // ```
-                let label_start = label.range.start - start_line_range.start;
+                //
+                // Labels past the end of a clipped line are moved to the clip
+                // marker.
+                let line_len = start_line_range.len();
+                let label_start = clamp_to_clip(
+                    range.start - start_line_range.start,
+                    line_len,
+                    start_clipped,
+                );
                 // Ensure that we print at least one caret, even when we
                 // have a zero-length source range.
-                let label_end =
-                    usize::max(label.range.end - start_line_range.start, label_start + 1);
+                let mut label_end = usize::max(
+                    clamp_to_clip(
+                        range.end - start_line_range.start,
+                        line_len + 1,
+                        start_clipped,
+                    ),
+                    label_start + 1,
+                );
+
+                // Labels that cover the line terminator end at the newline
+                // marker that is drawn in its place.
+                let mut newline_marker = false;
+                if label.display_column.is_none() {
+                    let source = files.source(label.file_id)?;
+                    let line_source = &source.as_ref()[start_line_range.clone()];
+                    let line_len = line_source
+                        .trim_end_matches(['\n', '\r', '\0'].as_ref())
+                        .len();
+                    if line_len < line_source.len() && range.end - start_line_range.start > line_len
+                    {
+                        newline_marker = true;
+                        label_end = line_len + self.config.chars.newline_marker.len_utf8();
+                    }
+                }
 
                 let line = labeled_file.get_or_insert_line(
                     start_line_index,
-                    start_line_range,
+                    (start_line_range, start_clipped),
                     start_line_number,
                 );
 
                 // Ensure that the single line labels are lexicographically
-                // sorted by the range of source code that they cover.
-                let index = match line.single_labels.binary_search_by(|(_, range, _)| {
-                    // `Range<usize>` doesn't implement `Ord`, so convert to `(usize, usize)`
-                    // to piggyback off its lexicographic comparison implementation.
-                    (range.start, range.end).cmp(&(label_start, label_end))
-                }) {
-                    // If the ranges are the same, order the labels in reverse
-                    // to how they were originally specified in the diagnostic.
-                    // This helps with printing in the renderer.
-                    Ok(index) | Err(index) => index,
-                };
+                // sorted by the range of source code that they cover. Labels
+                // with the same range are sorted by their message, then by
+                // their style, so that they are rendered in the same order
+                // regardless of the order they were specified in.
+                let index =
+                    match line
+                        .single_labels
+                        .binary_search_by(|(style, range, message, _)| {
+                            // `Range<usize>` doesn't implement `Ord`, so convert to `(usize, usize)`
+                            // to piggyback off its lexicographic comparison implementation.
+                            (range.start, range.end, *message, label_priority_key(style)).cmp(&(
+                                label_start,
+                                label_end,
+                                label.message.as_str(),
+                                label_priority_key(&label.style),
+                            ))
+                        }) {
+                        // If the labels are otherwise identical, order the labels in
+                        // reverse to how they were originally specified in the
+                        // diagnostic. This helps with printing in the renderer.
+                        Ok(index) | Err(index) => index,
+                    };
 
-                line.single_labels
-                    .insert(index, (label.style, label_start..label_end, &label.message));
+                line.single_labels.insert(
+                    index,
+                    (
+                        label.style,
+                        label_start..label_end,
+                        &label.message,
+                        label_hints(self.config, label),
+                    ),
+                );
+                line.padding = usize::max(line.padding, padding);
+                line.newline_marker |= newline_marker;
+                if let Some(detail) = &label.detail {
+                    line.details.push((
+                        label.style,
+                        label_hints(self.config, label),
+                        label_start,
+                        detail,
+                    ));
+                }
 
                 // If this line is not rendered, the SingleLabel is not visible.
                 line.must_render = true;
@@ -224,11 +682,15 @@ where
                 labeled_file.num_multi_labels += 1;
 
                 // First labeled line
-                let label_start = label.range.start - start_line_range.start;
+                let label_start = clamp_to_clip(
+                    range.start - start_line_range.start,
+                    start_line_range.len(),
+                    start_clipped,
+                );
 
                 let start_line = labeled_file.get_or_insert_line(
                     start_line_index,
-                    start_line_range.clone(),
+                    (start_line_range.clone(), start_clipped),
                     start_line_number,
                 );
 
@@ -249,12 +711,12 @@ where
                 // 7 │ │     _ 0 => "Buzz"
                 // ```
                 for line_index in (start_line_index + 1)..end_line_index {
-                    let line_range = files.line_range(label.file_id, line_index)?;
+                    let line = clipped_line(files, self.config, label.file_id, line_index)?;
                     let line_number = files.line_number(label.file_id, line_index)?;
 
                     outer_padding = std::cmp::max(outer_padding, count_digits(line_number));
 
-                    let line = labeled_file.get_or_insert_line(line_index, line_range, line_number);
+                    let line = labeled_file.get_or_insert_line(line_index, line, line_number);
 
                     line.multi_labels
                         .push((label_index, label.style, MultiLabel::Left));
@@ -274,11 +736,15 @@ where
                 // 8 │ │     _ _ => num
                 //   │ ╰──────────────^ `case` clauses have incompatible types
                 // ```
-                let label_end = label.range.end - end_line_range.start;
+                let label_end = clamp_to_clip(
+                    range.end - end_line_range.start,
+                    end_line_range.len(),
+                    end_clipped,
+                );
 
                 let end_line = labeled_file.get_or_insert_line(
                     end_line_index,
-                    end_line_range,
+                    (end_line_range, end_clipped),
                     end_line_number,
                 );
 
@@ -287,12 +753,36 @@ where
                     label.style,
                     MultiLabel::Bottom(label_end, &label.message),
                 ));
+                if let Some(detail) = &label.detail {
+                    end_line.details.push((
+                        label.style,
+                        label_hints(self.config, label),
+                        0,
+                        detail,
+                    ));
+                }
 
                 // The last line has to be rendered so the end of the label is visible.
                 end_line.must_render = true;
             }
         }
 
+        for (label, _) in &self.diagnostic.steps {
+            let line_index = files.line_index(label.file_id, label.range.start)?;
+            let line_number = files.line_number(label.file_id, line_index)?;
+            outer_padding = std::cmp::max(outer_padding, count_digits(line_number));
+        }
+
+        let is_focused = |file_id: FileId, line_range: &Range<usize>| match &self.focus {
+            Some((focus_file_id, range)) => {
+                *focus_file_id == file_id
+                    && range.start < line_range.end
+                    && (line_range.start < range.end || line_range.start == range.start)
+            }
+            None => false,
+        };
+        renderer.set_dimmed(self.focus.is_some())?;
+
         // Header and message
         //
         // ```text
@@ -314,6 +804,7 @@ where
         //   │         ^^ expected `Int` but found `String`
         //   │
         // ```
+        let mut cross_file_refs = Some(cross_file_refs);
         let mut labeled_files = labeled_files.into_iter().peekable();
         while let Some(labeled_file) = labeled_files.next() {
             let source = files.source(labeled_file.file_id)?;
@@ -324,20 +815,34 @@ where
             // ```text
             // ┌─ test:2:9
             // ```
-            if !labeled_file.lines.is_empty() {
-                renderer.render_snippet_start(
-                    outer_padding,
-                    &Locus {
-                        name: labeled_file.name,
-                        location: labeled_file.location,
-                    },
-                )?;
+            let is_empty = labeled_file.lines.is_empty() && labeled_file.synthetic_lines.is_empty();
+            if !is_empty {
+                match labeled_file.location {
+                    Some(location) => renderer.render_snippet_start(
+                        outer_padding,
+                        &Locus {
+                            name: labeled_file.name,
+                            location,
+                        },
+                    )?,
+                    None => {
+                        renderer.render_snippet_start_name(outer_padding, &labeled_file.name)?
+                    }
+                }
                 renderer.render_snippet_empty(
                     outer_padding,
                     self.diagnostic.severity,
                     labeled_file.num_multi_labels,
                     &[],
                 )?;
+                for _ in 0..self.config.code_frame_padding {
+                    renderer.render_snippet_empty(
+                        outer_padding,
+                        self.diagnostic.severity,
+                        labeled_file.num_multi_labels,
+                        &[],
+                    )?;
+                }
             }
 
             let mut lines = labeled_file
@@ -346,17 +851,93 @@ where
                 .filter(|(_, line)| line.must_render)
                 .peekable();
 
+            // The indentation that is common to all of the rendered lines,
+            // which is removed if requested.
+            let dedent = if self.config.dedent_common_indent {
+                let mut rendered_lines = Vec::new();
+                let mut remaining_lines = lines.clone();
+                while let Some((line_index, line)) = remaining_lines.next() {
+                    rendered_lines.push(&source[line.range.clone()]);
+                    // Include the lines that are rendered between two lines.
+                    if let Some((next_line_index, _)) = remaining_lines.peek() {
+                        if next_line_index.checked_sub(*line_index) == Some(2) {
+                            let (line_range, _) = clipped_line(
+                                files,
+                                self.config,
+                                labeled_file.file_id,
+                                line_index + 1,
+                            )?;
+                            rendered_lines.push(&source[line_range]);
+                        }
+                    }
+                }
+                common_indent(rendered_lines)
+            } else {
+                0
+            };
+
             while let Some((line_index, line)) = lines.next() {
+                let padded_source;
+                let undedented_source = match (line.padding, line.newline_marker, line.clipped) {
+                    (0, false, false) => &source[line.range.clone()],
+                    (padding, newline_marker, clipped) => {
+                        let line_source = source[line.range.clone()]
+                            .trim_end_matches(['\n', '\r', '\0'].as_ref());
+                        let mut line_source =
+                            with_clip_marker(self.config, line_source, clipped).into_owned();
+                        if newline_marker {
+                            line_source.push(self.config.chars.newline_marker);
+                        }
+                        padded_source = format!("{}{: >width$}", line_source, "", width = padding);
+                        &padded_source
+                    }
+                };
+                let (line_source, single_labels, multi_labels) = dedent_line(
+                    undedented_source,
+                    dedent,
+                    &line.single_labels,
+                    &line.multi_labels,
+                );
+                if self.focus.is_some() {
+                    // Clipped lines are focused by their full extent.
+                    let line_range = match line.clipped {
+                        true => files.line_range(labeled_file.file_id, *line_index)?,
+                        false => line.range.clone(),
+                    };
+                    renderer.set_dimmed(!is_focused(labeled_file.file_id, &line_range))?;
+                }
                 renderer.render_snippet_source(
                     outer_padding,
-                    line.number,
-                    &source[line.range.clone()],
+                    Some(line.number),
+                    line_source,
                     self.diagnostic.severity,
-                    &line.single_labels,
+                    &single_labels,
                     labeled_file.num_multi_labels,
-                    &line.multi_labels,
+                    &multi_labels,
                 )?;
 
+                // Label details
+                //
+                // ```text
+                //   │ │     the value is moved here,
+                //   │ │     so it can not be used again
+                // ```
+                let dedent = undedented_source.len() - line_source.len();
+                for (label_style, hints, start, detail) in &line.details {
+                    renderer.render_snippet_detail(
+                        outer_padding,
+                        line_source,
+                        self.diagnostic.severity,
+                        labeled_file.num_multi_labels,
+                        &multi_labels,
+                        *label_style,
+                        *hints,
+                        start.saturating_sub(dedent),
+                        detail,
+                    )?;
+                }
+                renderer.set_dimmed(self.focus.is_some())?;
+
                 // Check to see if we need to render any intermediate stuff
                 // before rendering the next line.
                 if let Some((next_line_index, next_line)) = lines.peek() {
@@ -375,14 +956,20 @@ where
                                 .get(&(line_index + 1))
                                 .map_or(&[][..], |line| &line.multi_labels[..]);
 
+                            let (line_range, clipped) =
+                                clipped_line(files, self.config, file_id, line_index + 1)?;
+                            let line_source =
+                                with_clip_marker(self.config, &source[line_range], clipped);
+                            let (line_source, _, labels) =
+                                dedent_line(&line_source, dedent, &[], labels);
                             renderer.render_snippet_source(
                                 outer_padding,
-                                files.line_number(file_id, line_index + 1)?,
-                                &source[files.line_range(file_id, line_index + 1)?],
+                                Some(files.line_number(file_id, line_index + 1)?),
+                                line_source,
                                 self.diagnostic.severity,
                                 &[],
                                 labeled_file.num_multi_labels,
-                                labels,
+                                &labels,
                             )?;
                         }
                         // More than one line between the current line and the next line.
@@ -403,9 +990,75 @@ where
                 }
             }
 
+            // Synthetic lines
+            //
+            // ```text
+            // ~ │ let x = y + 1;
+            //   │         ^ cannot find value `y` in this scope
+            // ```
+            for line in &labeled_file.synthetic_lines {
+                let line_source = match line.clip_len {
+                    Some(len) => with_clip_marker(self.config, &line.source[..len], true),
+                    None => Cow::Borrowed(line.source),
+                };
+                renderer.render_snippet_source(
+                    outer_padding,
+                    None,
+                    &line_source,
+                    self.diagnostic.severity,
+                    &line.single_labels,
+                    labeled_file.num_multi_labels,
+                    &[],
+                )?;
+                for (label_style, hints, start, detail) in &line.details {
+                    renderer.render_snippet_detail(
+                        outer_padding,
+                        &line_source,
+                        self.diagnostic.severity,
+                        labeled_file.num_multi_labels,
+                        &[],
+                        *label_style,
+                        *hints,
+                        *start,
+                        detail,
+                    )?;
+                }
+            }
+
+            // References to labels in other files, under the first snippet
+            // with a primary label.
+            //
+            // ```text
+            //   │ defined here (see lib.rs:1:8)
+            // ```
+            if labeled_file.max_label_style == LabelStyle::Primary {
+                for (locus, message) in cross_file_refs.take().into_iter().flatten() {
+                    renderer.render_snippet_reference(
+                        outer_padding,
+                        self.diagnostic.severity,
+                        &locus,
+                        message,
+                    )?;
+                }
+            }
+
+            if !is_empty {
+                for _ in 0..self.config.code_frame_padding {
+                    renderer.render_snippet_empty(
+                        outer_padding,
+                        self.diagnostic.severity,
+                        labeled_file.num_multi_labels,
+                        &[],
+                    )?;
+                }
+            }
+
             // Check to see if we should render a trailing border after the
             // final line of the snippet.
-            if labeled_files.peek().is_none() && self.diagnostic.notes.is_empty() {
+            if labeled_files.peek().is_none()
+                && self.diagnostic.steps.is_empty()
+                && self.diagnostic.notes.is_empty()
+            {
                 // We don't render a border if we are at the final newline
                 // without trailing notes, because it would end up looking too
                 // spaced-out in combination with the final new line.
@@ -420,6 +1073,70 @@ where
             }
         }
 
+        // Steps, in the order that they were given in
+        //
+        // ```text
+        // 1. value created here
+        //   ┌─ test:1:5
+        //   │
+        // 1 │ let x = String::new();
+        //   │     ^
+        //   │
+        // ```
+        let mut steps = self.diagnostic.steps.iter().enumerate().peekable();
+        while let Some((step_index, (label, description))) = steps.next() {
+            renderer.render_step_header(step_index + 1, description)?;
+            renderer.render_snippet_start(
+                outer_padding,
+                &Locus {
+                    name: files.name(label.file_id)?.to_string(),
+                    location: label_location(files, label)?,
+                },
+            )?;
+            renderer.render_snippet_empty(outer_padding, self.diagnostic.severity, 0, &[])?;
+
+            let source = files.source(label.file_id)?;
+            let line_index = files.line_index(label.file_id, label.range.start)?;
+            let (line_range, clipped) =
+                clipped_line(files, self.config, label.file_id, line_index)?;
+            let line = &source.as_ref()[line_range.clone()];
+            let line_len = line.trim_end_matches(['\n', '\r', '\0'].as_ref()).len();
+
+            // Multi-line labels are shown up to the end of their first line,
+            // and labels pointing past the end of the line are shown at its end.
+            let (label_start, label_end) = match label.display_column {
+                Some(column) => {
+                    let (range, _) = display_column_range(line, column, self.config.tab_width);
+                    let label_start = usize::min(range.start, line_len);
+                    (label_start, label_start + 1)
+                }
+                None => {
+                    let label_start =
+                        clamp_to_clip(label.range.start - line_range.start, line_len, clipped);
+                    let label_end = usize::min(label.range.end - line_range.start, line_len);
+                    (label_start, usize::max(label_end, label_start + 1))
+                }
+            };
+
+            renderer.render_snippet_source(
+                outer_padding,
+                Some(files.line_number(label.file_id, line_index)?),
+                &with_clip_marker(self.config, line, clipped),
+                self.diagnostic.severity,
+                &[(
+                    label.style,
+                    label_start..label_end,
+                    &label.message,
+                    label_hints(self.config, label),
+                )],
+                0,
+                &[],
+            )?;
+            if steps.peek().is_some() || !self.diagnostic.notes.is_empty() {
+                renderer.render_snippet_empty(outer_padding, self.diagnostic.severity, 0, &[])?;
+            }
+        }
+
         // Additional notes
         //
         // ```text
@@ -429,10 +1146,699 @@ where
         for note in &self.diagnostic.notes {
             renderer.render_snippet_note(outer_padding, note)?;
         }
+        renderer.set_dimmed(false)?;
         renderer.render_empty()
     }
 }
 
+/// Output a compact diagnostic, for diagnostics with a single, single-line label.
+pub struct CompactDiagnostic<'diagnostic, 'config, FileId> {
+    diagnostic: &'diagnostic Diagnostic<FileId>,
+    config: &'config Config,
+    label: &'diagnostic Label<FileId>,
+    line_index: usize,
+    hints: LabelHints,
+}
+
+impl<'diagnostic, 'config, FileId> CompactDiagnostic<'diagnostic, 'config, FileId>
+where
+    FileId: Copy + PartialEq,
+{
+    /// Returns `None` if the diagnostic can not be rendered compactly.
+    pub fn new<'files>(
+        diagnostic: &'diagnostic Diagnostic<FileId>,
+        config: &'config Config,
+        files: &'files impl Files<'files, FileId = FileId>,
+    ) -> Result<Option<CompactDiagnostic<'diagnostic, 'config, FileId>>, Error>
+    where
+        FileId: 'files,
+    {
+        let label = match diagnostic.labels.as_slice() {
+            [label]
+                if diagnostic.notes.is_empty()
+                    && diagnostic.steps.is_empty()
+                    && label.detail.is_none()
+                    && label.display_column.is_none()
+                    && label.synthetic_line.is_none() =>
+            {
+                label
+            }
+            _ => return Ok(None),
+        };
+
+        let line_index = files.line_index(label.file_id, label.range.start)?;
+        if line_index != files.line_index(label.file_id, label.range.end)? {
+            return Ok(None);
+        }
+
+        Ok(Some(CompactDiagnostic {
+            diagnostic,
+            config,
+            label,
+            line_index,
+            hints: label_hints(config, label),
+        }))
+    }
+
+    pub fn render<'files>(
+        &self,
+        files: &'files impl Files<'files, FileId = FileId>,
+        renderer: &mut Renderer<'_, '_>,
+    ) -> Result<(), Error>
+    where
+        FileId: 'files,
+    {
+        let label = self.label;
+        let source = files.source(label.file_id)?;
+        let (line_range, clipped) =
+            clipped_line(files, self.config, label.file_id, self.line_index)?;
+        let line_number = files.line_number(label.file_id, self.line_index)?;
+
+        // Located header
+        //
+        // ```text
+        // test:2:9: error[E0001]: unexpected type in `+` application
+        // ```
+        renderer.render_header(
+            Some(&Locus {
+                name: files.name(label.file_id)?.to_string(),
+                location: files.location(label.file_id, label.range.start)?,
+            }),
+            self.diagnostic.severity,
+            self.diagnostic.code.as_deref(),
+            self.diagnostic.message.as_str(),
+        )?;
+
+        // Source line, with the label underneath
+        //
+        // ```text
+        // 2 │ (+ test "")
+        //   │         ^^ expected `Int` but found `String`
+        // ```
+        let line_len = line_range.len();
+        let label_start = clamp_to_clip(label.range.start - line_range.start, line_len, clipped);
+        // Ensure that we print at least one caret, even when we
+        // have a zero-length source range.
+        let label_end = usize::max(
+            clamp_to_clip(label.range.end - line_range.start, line_len + 1, clipped),
+            label_start + 1,
+        );
+
+        renderer.render_snippet_source(
+            count_digits(line_number),
+            Some(line_number),
+            &with_clip_marker(self.config, &source.as_ref()[line_range], clipped),
+            self.diagnostic.severity,
+            &[(
+                label.style,
+                label_start..label_end,
+                &label.message,
+                self.hints,
+            )],
+            0,
+            &[],
+        )
+    }
+}
+
+/// The width to wrap narrow diagnostics to if the terminal width is not known.
+const DEFAULT_NARROW_WIDTH: usize = 40;
+
+/// Greedily wrap text to the given display width, breaking words that do not
+/// fit on a line of their own.
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    use unicode_width::UnicodeWidthStr;
+
+    let width = usize::max(width, 1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.width() + 1 + word.width() <= width {
+            line.push(' ');
+            line.push_str(word);
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        for ch in word.chars() {
+            if !line.is_empty() && line.width() + ch.to_string().width() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.push(ch);
+        }
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Output a diagnostic for narrow terminals, with messages below their labels.
+pub struct NarrowDiagnostic<'diagnostic, 'config, FileId> {
+    diagnostic: &'diagnostic Diagnostic<FileId>,
+    config: &'config Config,
+}
+
+impl<'diagnostic, 'config, FileId> NarrowDiagnostic<'diagnostic, 'config, FileId>
+where
+    FileId: Copy + PartialEq,
+{
+    pub fn new(
+        diagnostic: &'diagnostic Diagnostic<FileId>,
+        config: &'config Config,
+    ) -> NarrowDiagnostic<'diagnostic, 'config, FileId> {
+        NarrowDiagnostic { diagnostic, config }
+    }
+
+    pub fn render<'files>(
+        &self,
+        files: &'files impl Files<'files, FileId = FileId>,
+        renderer: &mut Renderer<'_, '_>,
+    ) -> Result<(), Error>
+    where
+        FileId: 'files,
+    {
+        let width = self.config.terminal_width.unwrap_or(DEFAULT_NARROW_WIDTH);
+
+        // Header and message
+        //
+        // ```text
+        // error[E0001]: unexpected type in `+` application
+        // ```
+        renderer.render_header(
+            None,
+            self.diagnostic.severity,
+            self.diagnostic.code.as_deref(),
+            self.diagnostic.message.as_str(),
+        )?;
+
+        // Order the labels by file, in the order that the files first appear,
+        // then by their position in the file.
+        let mut file_ids = Vec::new();
+        for label in &self.diagnostic.labels {
+            if !file_ids.contains(&label.file_id) {
+                file_ids.push(label.file_id);
+            }
+        }
+        let mut labels = Vec::with_capacity(self.diagnostic.labels.len());
+        let mut outer_padding = 0;
+        // The display width of the widest source line of each file.
+        let mut line_widths = vec![0; file_ids.len()];
+        for label in &self.diagnostic.labels {
+            let line_index = files.line_index(label.file_id, label.range.start)?;
+            let line_number = files.line_number(label.file_id, line_index)?;
+            outer_padding = std::cmp::max(outer_padding, count_digits(line_number));
+
+            let file_index = file_ids.iter().position(|id| *id == label.file_id);
+            if let Some(file_index) = file_index {
+                let source = files.source(label.file_id)?;
+                let (line_range, _) = clipped_line(files, self.config, label.file_id, line_index)?;
+                let line = source.as_ref()[line_range].trim();
+                let (_, columns) = expand_tabs(line, self.config.tab_width);
+                let line_width = columns.last().copied().unwrap_or(0);
+                line_widths[file_index] = usize::max(line_widths[file_index], line_width);
+            }
+            labels.push((
+                file_index,
+                label.range.start,
+                label,
+                line_index,
+                line_number,
+            ));
+        }
+        labels.sort_by_key(|(file_index, start, _, _, _)| (*file_index, *start));
+
+        // Labels
+        //
+        // ```text
+        // ┌─ test:2:9
+        // 2│ (+ test "")
+        //  │         ^^
+        //  │ expected `Int` but found
+        //  │ `String`
+        // ```
+        for (file_index, _, label, line_index, line_number) in labels {
+            // Files with short lines are wrapped to the width of their lines,
+            // but not to less than the default width, if requested.
+            let width = match file_index {
+                Some(file_index) if self.config.per_file_width => {
+                    let min_width = usize::min(width, DEFAULT_NARROW_WIDTH);
+                    let line_width = line_widths[file_index] + outer_padding + 2;
+                    usize::max(usize::min(line_width, width), min_width)
+                }
+                Some(_) | None => width,
+            };
+
+            renderer.render_narrow_locus(&Locus {
+                name: files.name(label.file_id)?.to_string(),
+                location: label_location(files, label)?,
+            })?;
+
+            let (line, label_range) = dedented_label_line(files, self.config, label, line_index)?;
+            renderer.render_narrow_source(
+                outer_padding,
+                line_number,
+                &line,
+                self.diagnostic.severity,
+                &(
+                    label.style,
+                    label_range,
+                    "",
+                    label_hints(self.config, label),
+                ),
+            )?;
+            if !label.message.is_empty() {
+                for message_line in
+                    wrap_text(&label.message, width.saturating_sub(outer_padding + 2))
+                {
+                    renderer.render_narrow_message(
+                        outer_padding,
+                        self.diagnostic.severity,
+                        label.style,
+                        label_hints(self.config, label),
+                        &message_line,
+                    )?;
+                }
+            }
+            if let Some(detail) = &label.detail {
+                for detail_line in detail
+                    .lines()
+                    .flat_map(|line| wrap_text(line, width.saturating_sub(outer_padding + 2)))
+                {
+                    renderer.render_narrow_message(
+                        outer_padding,
+                        self.diagnostic.severity,
+                        label.style,
+                        label_hints(self.config, label),
+                        &detail_line,
+                    )?;
+                }
+            }
+        }
+
+        // Additional notes
+        //
+        // ```text
+        // = expected type `Int`
+        //      found type `String`
+        // ```
+        for note in &self.diagnostic.notes {
+            let note = note
+                .lines()
+                .flat_map(|line| {
+                    let indent = line.len() - line.trim_start().len();
+                    wrap_text(line, width.saturating_sub(indent + 2))
+                        .into_iter()
+                        .map(move |wrapped| format!("{}{}", &line[..indent], wrapped))
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            renderer.render_snippet_note(0, &note)?;
+        }
+        renderer.render_empty()
+    }
+}
+
+/// Get the line that a label starts on, without its leading indentation, along
+/// with the range of the label in that line.
+///
+/// Multi-line labels are shown up to the end of their first line, and labels
+/// pointing past the end of the line are shown at its end. Labels past the end
+/// of a clipped line are shown at the clip marker.
+fn dedented_label_line<'files, FileId>(
+    files: &'files impl Files<'files, FileId = FileId>,
+    config: &Config,
+    label: &Label<FileId>,
+    line_index: usize,
+) -> Result<(String, Range<usize>), Error>
+where
+    FileId: 'files + Copy,
+{
+    let source = files.source(label.file_id)?;
+    let (line_range, clipped) = clipped_line(files, config, label.file_id, line_index)?;
+    let line = source.as_ref()[line_range.clone()].trim_end_matches(['\n', '\r'].as_ref());
+
+    let (label_start, label_end) = match label.display_column {
+        Some(column) => {
+            let (range, _) = display_column_range(line, column, config.tab_width);
+            let label_start = usize::min(range.start, line.len());
+            (label_start, usize::min(range.end, line.len()))
+        }
+        None => (
+            clamp_to_clip(label.range.start - line_range.start, line.len(), clipped),
+            usize::min(label.range.end - line_range.start, line.len()),
+        ),
+    };
+    // Remove leading indentation, but not if the label starts within it.
+    let indent = usize::min(line.len() - line.trim_start().len(), label_start);
+    let label_range = (label_start - indent)
+        ..usize::max(label_end.saturating_sub(indent), label_start - indent + 1);
+
+    let line = with_clip_marker(config, &line[indent..], clipped).into_owned();
+    Ok((line, label_range))
+}
+
+/// The width to wrap Elm-style diagnostics to if the terminal width is not known.
+const DEFAULT_ELM_WIDTH: usize = 80;
+
+/// The number of columns that source lines are indented by in Elm-style diagnostics.
+const ELM_SOURCE_INDENT: usize = 4;
+
+/// Output a spacious diagnostic in the style of the Elm compiler.
+pub struct ElmDiagnostic<'diagnostic, 'config, FileId> {
+    diagnostic: &'diagnostic Diagnostic<FileId>,
+    config: &'config Config,
+}
+
+impl<'diagnostic, 'config, FileId> ElmDiagnostic<'diagnostic, 'config, FileId>
+where
+    FileId: Copy + PartialEq,
+{
+    pub fn new(
+        diagnostic: &'diagnostic Diagnostic<FileId>,
+        config: &'config Config,
+    ) -> ElmDiagnostic<'diagnostic, 'config, FileId> {
+        ElmDiagnostic { diagnostic, config }
+    }
+
+    pub fn render<'files>(
+        &self,
+        files: &'files impl Files<'files, FileId = FileId>,
+        renderer: &mut Renderer<'_, '_>,
+    ) -> Result<(), Error>
+    where
+        FileId: 'files,
+    {
+        let width = self.config.terminal_width.unwrap_or(DEFAULT_ELM_WIDTH);
+
+        // Header and message
+        //
+        // ```text
+        // error[E0001]: unexpected type in `+` application
+        // ```
+        renderer.render_header(
+            None,
+            self.diagnostic.severity,
+            self.diagnostic.code.as_deref(),
+            self.diagnostic.message.as_str(),
+        )?;
+
+        // Order the labels by file, in the order that the files first appear,
+        // then by their position in the file.
+        let mut file_ids = Vec::new();
+        for label in &self.diagnostic.labels {
+            if !file_ids.contains(&label.file_id) {
+                file_ids.push(label.file_id);
+            }
+        }
+        let mut labels = Vec::with_capacity(self.diagnostic.labels.len());
+        let mut outer_padding = 0;
+        for label in &self.diagnostic.labels {
+            let line_index = files.line_index(label.file_id, label.range.start)?;
+            let line_number = files.line_number(label.file_id, line_index)?;
+            outer_padding = std::cmp::max(outer_padding, count_digits(line_number));
+            let file_index = file_ids.iter().position(|id| *id == label.file_id);
+            labels.push((file_index, label, line_index, line_number));
+        }
+        labels.sort_by_key(|(file_index, label, _, _)| (*file_index, label.range.start));
+
+        // Labels
+        //
+        // ```text
+        //
+        // test:2:9
+        //
+        //     2│ (+ test "")
+        //      │         ^^
+        //
+        // expected `Int` but found `String`
+        // ```
+        for (_, label, line_index, line_number) in labels {
+            renderer.render_empty()?;
+            renderer.render_locus(&Locus {
+                name: files.name(label.file_id)?.to_string(),
+                location: label_location(files, label)?,
+            })?;
+            renderer.render_empty()?;
+
+            let (line, label_range) = dedented_label_line(files, self.config, label, line_index)?;
+            renderer.render_narrow_source(
+                outer_padding + ELM_SOURCE_INDENT,
+                line_number,
+                &line,
+                self.diagnostic.severity,
+                &(
+                    label.style,
+                    label_range,
+                    "",
+                    label_hints(self.config, label),
+                ),
+            )?;
+
+            let paragraph = label
+                .detail
+                .iter()
+                .flat_map(|detail| detail.lines())
+                .fold(label.message.clone(), |paragraph, line| {
+                    format!("{} {}", paragraph, line)
+                });
+            if !paragraph.trim().is_empty() {
+                renderer.render_empty()?;
+                for message_line in wrap_text(&paragraph, width) {
+                    renderer.render_paragraph(
+                        Some((
+                            self.diagnostic.severity,
+                            label.style,
+                            label_hints(self.config, label),
+                        )),
+                        &message_line,
+                    )?;
+                }
+            }
+        }
+
+        // Additional notes, each as its own paragraph
+        //
+        // ```text
+        //
+        // expected type `Int`
+        //      found type `String`
+        // ```
+        for note in &self.diagnostic.notes {
+            renderer.render_empty()?;
+            for line in note.lines() {
+                let indent = line.len() - line.trim_start().len();
+                for wrapped in wrap_text(line, width.saturating_sub(indent)) {
+                    renderer.render_paragraph(None, &format!("{}{}", &line[..indent], wrapped))?;
+                }
+            }
+        }
+        renderer.render_empty()
+    }
+}
+
+/// The terminal width to assume when rendering side by side, if none is configured.
+const DEFAULT_SIDE_BY_SIDE_WIDTH: usize = 80;
+
+/// Expand the tabs in a line of source, returning the expanded text along with
+/// the display column of each byte index of the line, and of the end of the line.
+fn expand_tabs(line: &str, tab_width: usize) -> (String, Vec<usize>) {
+    use unicode_width::UnicodeWidthChar;
+
+    let mut text = String::with_capacity(line.len());
+    let mut columns = Vec::with_capacity(line.len() + 1);
+    let mut column = 0;
+
+    for ch in line.chars() {
+        let width = match (ch, tab_width) {
+            ('\t', 0) => 0, // Guard divide-by-zero
+            ('\t', _) => tab_width - (column % tab_width),
+            (ch, _) => ch.width().unwrap_or(0),
+        };
+        match ch {
+            '\t' => (0..width).for_each(|_| text.push(' ')),
+            _ => text.push(ch),
+        }
+        columns.resize(columns.len() + ch.len_utf8(), column);
+        column += width;
+    }
+    columns.push(column);
+
+    (text, columns)
+}
+
+/// Output the source code of two labels in parallel columns.
+pub struct SideBySideDiagnostic<'diagnostic, 'config, FileId> {
+    left: &'diagnostic Label<FileId>,
+    right: &'diagnostic Label<FileId>,
+    config: &'config Config,
+}
+
+impl<'diagnostic, 'config, FileId> SideBySideDiagnostic<'diagnostic, 'config, FileId>
+where
+    FileId: Copy + PartialEq,
+{
+    pub fn new(
+        left: &'diagnostic Label<FileId>,
+        right: &'diagnostic Label<FileId>,
+        config: &'config Config,
+    ) -> SideBySideDiagnostic<'diagnostic, 'config, FileId> {
+        SideBySideDiagnostic {
+            left,
+            right,
+            config,
+        }
+    }
+
+    pub fn render<'files>(
+        &self,
+        files: &'files impl Files<'files, FileId = FileId>,
+        renderer: &mut Renderer<'_, '_>,
+    ) -> Result<(), Error>
+    where
+        FileId: 'files,
+    {
+        let width = self
+            .config
+            .terminal_width
+            .unwrap_or(DEFAULT_SIDE_BY_SIDE_WIDTH);
+        // Leave room for the divider between the columns.
+        let column_width = width.saturating_sub(3) / 2;
+
+        let left = self.block(files, self.left)?;
+        let right = self.block(files, self.right)?;
+
+        // ```text
+        //   ┌─ old.rs:1:4          │   ┌─ new.rs:1:4
+        // 1 │ fn add(a: i32) {     │ 1 │ fn add(a: i64) {
+        //   │        ^^^^^^ before │   │        ------ after
+        // ```
+        for row in 0..usize::max(left.len(), right.len()) {
+            renderer.render_side_by_side_row(
+                column_width,
+                Severity::Note,
+                left.get(row).map_or(&[][..], |parts| &parts[..]),
+                right.get(row).map_or(&[][..], |parts| &parts[..]),
+            )?;
+        }
+        renderer.render_empty()
+    }
+
+    /// Lay out the rows for the lines covered by a label.
+    fn block<'files>(
+        &self,
+        files: &'files impl Files<'files, FileId = FileId>,
+        label: &Label<FileId>,
+    ) -> Result<Vec<Vec<SidePart>>, Error>
+    where
+        FileId: 'files,
+    {
+        let source = files.source(label.file_id)?;
+        let source = source.as_ref();
+        let start_line_index = files.line_index(label.file_id, label.range.start)?;
+        let end_line_index = files.line_index(label.file_id, label.range.end)?;
+        let end_line_number = files.line_number(label.file_id, end_line_index)?;
+        let outer_padding = count_digits(end_line_number);
+        let gutter = || {
+            (
+                SideStyle::Source,
+                format!("{: >width$} ", "", width = outer_padding),
+            )
+        };
+        let border = || {
+            (
+                SideStyle::Border,
+                self.config.chars.source_border_left.to_string(),
+            )
+        };
+
+        let mut rows = Vec::new();
+
+        // ```text
+        //   ┌─ old.rs:1:4
+        // ```
+        let location = files.location(label.file_id, label.range.start)?;
+        let mut name = files.name(label.file_id)?.to_string();
+        if name.is_empty() {
+            name = self.config.anonymous_file_name.clone();
+        }
+        rows.push(vec![
+            gutter(),
+            (SideStyle::Border, self.config.chars.snippet_start.clone()),
+            (
+                SideStyle::Source,
+                format!(
+                    " {}:{}:{}",
+                    name, location.line_number, location.column_number,
+                ),
+            ),
+        ]);
+
+        // ```text
+        // 1 │ fn add(a: i32) {
+        //   │        ^^^^^^ before
+        // ```
+        for line_index in start_line_index..=end_line_index {
+            let (line_range, clipped) =
+                clipped_line(files, self.config, label.file_id, line_index)?;
+            let line = source[line_range.clone()].trim_end_matches(['\n', '\r', '\0'].as_ref());
+            let (text, columns) = expand_tabs(line, self.config.tab_width);
+            let text = with_clip_marker(self.config, &text, clipped);
+
+            // Labels continue from the start of the indented text on each
+            // line after their first, and up to the end of each line before
+            // their last.
+            let caret_start = if line_index == start_line_index {
+                columns[usize::min(label.range.start - line_range.start, line.len())]
+            } else {
+                columns[line.len() - line.trim_start().len()]
+            };
+            let caret_end = if line_index == end_line_index {
+                columns[usize::min(label.range.end - line_range.start, line.len())]
+            } else {
+                columns[line.len()]
+            };
+            let caret = match label.style {
+                LabelStyle::Primary => self.config.chars.single_primary_caret,
+                LabelStyle::Secondary => self.config.chars.single_secondary_caret,
+            };
+            let mut carets = caret
+                .to_string()
+                .repeat(usize::max(caret_end.saturating_sub(caret_start), 1));
+            if line_index == end_line_index && !label.message.is_empty() {
+                carets.push(' ');
+                carets.push_str(&label.message);
+            }
+
+            let line_number = files.line_number(label.file_id, line_index)?;
+            let line_number = match self.config.gutter_align {
+                GutterAlign::Left => format!("{: <width$}", line_number, width = outer_padding),
+                GutterAlign::Right => format!("{: >width$}", line_number, width = outer_padding),
+            };
+            rows.push(vec![
+                (SideStyle::LineNumber, line_number),
+                (SideStyle::Source, " ".to_owned()),
+                border(),
+                (SideStyle::Source, format!(" {}", text)),
+            ]);
+            rows.push(vec![
+                gutter(),
+                border(),
+                (
+                    SideStyle::Source,
+                    format!(" {: >width$}", "", width = caret_start),
+                ),
+                (SideStyle::Label(label.style), carets),
+            ]);
+        }
+
+        Ok(rows)
+    }
+}
+
 /// Output a short diagnostic, with a line number, severity, and message.
 pub struct ShortDiagnostic<'diagnostic, FileId> {
     diagnostic: &'diagnostic Diagnostic<FileId>,
@@ -474,7 +1880,7 @@ where
             renderer.render_header(
                 Some(&Locus {
                     name: files.name(label.file_id)?.to_string(),
-                    location: files.location(label.file_id, label.range.start)?,
+                    location: label_location(files, label)?,
                 }),
                 self.diagnostic.severity,
                 self.diagnostic.code.as_deref(),
@@ -511,3 +1917,135 @@ where
         Ok(())
     }
 }
+
+/// Output the whole of a file, with the labels of many diagnostics overlaid.
+pub struct AnnotatedFile<'diagnostic, 'config, FileId> {
+    file_id: FileId,
+    diagnostics: &'diagnostic [Diagnostic<FileId>],
+    config: &'config Config,
+}
+
+impl<'diagnostic, 'config, FileId> AnnotatedFile<'diagnostic, 'config, FileId>
+where
+    FileId: Copy + PartialEq,
+{
+    pub fn new(
+        file_id: FileId,
+        diagnostics: &'diagnostic [Diagnostic<FileId>],
+        config: &'config Config,
+    ) -> AnnotatedFile<'diagnostic, 'config, FileId> {
+        AnnotatedFile {
+            file_id,
+            diagnostics,
+            config,
+        }
+    }
+
+    pub fn render<'files>(
+        &self,
+        files: &'files impl Files<'files, FileId = FileId>,
+        renderer: &mut Renderer<'_, '_>,
+    ) -> Result<(), Error>
+    where
+        FileId: 'files,
+    {
+        let source = files.source(self.file_id)?;
+        let source = source.as_ref();
+
+        // Don't show the empty line after a trailing newline.
+        let mut last_line_index = files.line_index(self.file_id, source.len())?;
+        if last_line_index > 0 && files.line_range(self.file_id, last_line_index)?.is_empty() {
+            last_line_index -= 1;
+        }
+        let outer_padding = count_digits(files.line_number(self.file_id, last_line_index)?);
+
+        // Tag the message of each label with the severity of its diagnostic,
+        // falling back to the message of the diagnostic.
+        let mut labels = Vec::new();
+        for diagnostic in self.diagnostics {
+            let severity_name = severity_name(diagnostic.severity);
+            for label in &diagnostic.labels {
+                if label.file_id != self.file_id {
+                    continue;
+                }
+                let message = if label.message.is_empty() {
+                    &diagnostic.message
+                } else {
+                    &label.message
+                };
+                let message = if message.is_empty() {
+                    severity_name.to_owned()
+                } else {
+                    format!("{}: {}", severity_name, message)
+                };
+                let line_index = files.line_index(self.file_id, label.range.start)?;
+                labels.push((diagnostic.severity, line_index, label, message));
+            }
+        }
+
+        // ```text
+        //   ┌─ test:1:1
+        //   │
+        // ```
+        renderer.render_snippet_start(
+            outer_padding,
+            &Locus {
+                name: files.name(self.file_id)?.to_string(),
+                location: files.location(self.file_id, 0)?,
+            },
+        )?;
+        renderer.render_snippet_empty(outer_padding, Severity::Help, 0, &[])?;
+
+        // ```text
+        // 1 │ let x = 1 + "";
+        //   │             ^^ error: expected `Int`
+        // 2 │ let y = x;
+        // ```
+        for line_index in 0..=last_line_index {
+            let (line_range, clipped) = clipped_line(files, self.config, self.file_id, line_index)?;
+            let line = &source[line_range.clone()];
+            let line_len = line.trim_end_matches(['\n', '\r', '\0'].as_ref()).len();
+
+            // Multi-line labels are shown up to the end of their first line,
+            // and labels past the end of a clipped line at the clip marker.
+            let mut severity = Severity::Help;
+            let mut single_labels = Vec::new();
+            for (label_severity, label_line_index, label, message) in &labels {
+                if *label_line_index != line_index {
+                    continue;
+                }
+                let start = clamp_to_clip(label.range.start - line_range.start, line_len, clipped);
+                let end = usize::min(label.range.end - line_range.start, line_len);
+                // Primary labels are colored by the severity of their own
+                // diagnostic, rather than by the most severe one on the line.
+                let mut hints = label_hints(self.config, label);
+                if label.style == LabelStyle::Primary {
+                    hints.severity = hints.severity.or(Some(*label_severity));
+                }
+                single_labels.push((
+                    label.style,
+                    start..usize::max(end, start + 1),
+                    message.as_str(),
+                    hints,
+                ));
+                severity = std::cmp::max(severity, *label_severity);
+            }
+            single_labels.sort_by_key(|(style, range, message, _)| {
+                (range.start, range.end, *message, label_priority_key(style))
+            });
+
+            renderer.render_snippet_source(
+                outer_padding,
+                Some(files.line_number(self.file_id, line_index)?),
+                &with_clip_marker(self.config, line, clipped),
+                severity,
+                &single_labels,
+                0,
+                &[],
+            )?;
+        }
+        renderer.render_snippet_empty(outer_padding, Severity::Help, 0, &[])?;
+
+        Ok(())
+    }
+}
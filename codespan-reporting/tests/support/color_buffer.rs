@@ -17,6 +17,7 @@ use termcolor::{ColorSpec, WriteColor};
 /// - Bold as `bold`
 /// - Underline as `underline`
 /// - Intense as `bright`
+/// - Dimmed as `dimmed`
 ///
 /// For example, the style "intense, bold red foreground" would be printed as:
 ///
@@ -119,6 +120,11 @@ impl WriteColor for ColorBuffer {
             write!(self, "bright")?;
         }
 
+        if spec.dimmed() {
+            first = write_first(first, self)?;
+            write!(self, "dimmed")?;
+        }
+
         write!(self, "}}")?;
 
         Ok(())
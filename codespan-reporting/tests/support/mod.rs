@@ -1,6 +1,6 @@
 use codespan_reporting::diagnostic::Diagnostic;
 use codespan_reporting::files::Files;
-use codespan_reporting::term::{emit, Config};
+use codespan_reporting::term::{emit, Config, EmitSummary};
 use termcolor::{Buffer, WriteColor};
 
 mod color_buffer;
@@ -15,17 +15,48 @@ pub struct TestData<'files, F: Files<'files>> {
 impl<'files, F: Files<'files>> TestData<'files, F> {
     fn emit<W: WriteColor>(&'files self, mut writer: W, config: &Config) -> W {
         for diagnostic in &self.diagnostics {
-            emit(&mut writer, config, &self.files, &diagnostic).unwrap();
+            emit(&mut writer, config, &self.files, diagnostic).unwrap();
         }
         writer
     }
 
     pub fn emit_color(&'files self, config: &Config) -> String {
-        self.emit(ColorBuffer::new(), &config).into_string()
+        self.emit(ColorBuffer::new(), config).into_string()
     }
 
     pub fn emit_no_color(&'files self, config: &Config) -> String {
-        let buffer = self.emit(Buffer::no_color(), &config);
+        let buffer = self.emit(Buffer::no_color(), config);
         String::from_utf8_lossy(buffer.as_slice()).into_owned()
     }
+
+    /// Emit all of the diagnostics at once, using a batch emitter like
+    /// `term::emit_by_severity`.
+    pub fn emit_batch_color(
+        &'files self,
+        emit_batch: impl FnOnce(
+            &mut dyn WriteColor,
+            &'files F,
+            &'files [Diagnostic<F::FileId>],
+        ) -> EmitSummary,
+    ) -> String {
+        let mut writer = ColorBuffer::new();
+        emit_batch(&mut writer, &self.files, &self.diagnostics);
+        writer.into_string()
+    }
+
+    /// Emit all of the diagnostics at once, using a batch emitter like
+    /// `term::emit_by_severity`, and check the number of bytes it reports.
+    pub fn emit_batch_no_color(
+        &'files self,
+        emit_batch: impl FnOnce(
+            &mut dyn WriteColor,
+            &'files F,
+            &'files [Diagnostic<F::FileId>],
+        ) -> EmitSummary,
+    ) -> String {
+        let mut writer = Buffer::no_color();
+        let summary = emit_batch(&mut writer, &self.files, &self.diagnostics);
+        assert_eq!(summary.bytes_written, writer.len());
+        String::from_utf8_lossy(writer.as_slice()).into_owned()
+    }
 }
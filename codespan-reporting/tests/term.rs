@@ -1,6 +1,6 @@
 use codespan_reporting::diagnostic::{Diagnostic, Label};
 use codespan_reporting::files::{SimpleFile, SimpleFiles};
-use codespan_reporting::term::{termcolor::Color, Chars, Config, DisplayStyle, Styles};
+use codespan_reporting::term::{self, termcolor::Color, Chars, Config, DisplayStyle, Styles};
 
 mod support;
 
@@ -81,6 +81,28 @@ macro_rules! test_emit {
             insta::assert_snapshot!(TEST_DATA.emit_no_color(&config));
         }
     };
+    (compact_color) => {
+        #[test]
+        fn compact_color() {
+            let config = Config {
+                display_style: DisplayStyle::Compact,
+                ..TEST_CONFIG.clone()
+            };
+
+            insta::assert_snapshot!(TEST_DATA.emit_color(&config));
+        }
+    };
+    (compact_no_color) => {
+        #[test]
+        fn compact_no_color() {
+            let config = Config {
+                display_style: DisplayStyle::Compact,
+                ..TEST_CONFIG.clone()
+            };
+
+            insta::assert_snapshot!(TEST_DATA.emit_no_color(&config));
+        }
+    };
     (rich_ascii_no_color) => {
         #[test]
         fn rich_ascii_no_color() {
@@ -90,6 +112,18 @@ macro_rules! test_emit {
                 ..TEST_CONFIG.clone()
             };
 
+            insta::assert_snapshot!(TEST_DATA.emit_no_color(&config));
+        }
+    };
+    (rich_minimal_no_color) => {
+        #[test]
+        fn rich_minimal_no_color() {
+            let config = Config {
+                display_style: DisplayStyle::Rich,
+                chars: Chars::minimal(),
+                ..TEST_CONFIG.clone()
+            };
+
             insta::assert_snapshot!(TEST_DATA.emit_no_color(&config));
         }
     };
@@ -823,6 +857,55 @@ mod tab_columns {
     }
 }
 
+mod tab_crossing {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFiles<&'static str, String>> = {
+            let mut files = SimpleFiles::new();
+
+            let file_id = files.add(
+                "tab_crossing",
+                [
+                    "block:",
+                    "\tkey:\tvalue",
+                    "\tmore",
+                    "end",
+                ]
+                .join("\n"),
+            );
+
+            let diagnostics = vec![
+                Diagnostic::warning()
+                    .with_message("label starting on a tab")
+                    .with_labels(vec![Label::primary(file_id, 12..18).with_message("the tab is covered")]),
+                Diagnostic::warning()
+                    .with_message("multi-line label starting on a tab")
+                    .with_labels(vec![Label::primary(file_id, 12..24).with_message("the value")]),
+            ];
+
+            TestData { files, diagnostics }
+        };
+    }
+
+    #[test]
+    fn tab_width_default_no_color() {
+        let config = TEST_CONFIG.clone();
+
+        insta::assert_snapshot!(TEST_DATA.emit_no_color(&config));
+    }
+
+    #[test]
+    fn tab_width_3_no_color() {
+        let config = Config {
+            tab_width: 3,
+            ..TEST_CONFIG.clone()
+        };
+
+        insta::assert_snapshot!(TEST_DATA.emit_no_color(&config));
+    }
+}
+
 /// Based on:
 /// - https://github.com/TheSamsa/rust/blob/75cf41afb468152611212271bae026948cd3ba46/src/test/ui/codemap_tests/unicode.stderr
 mod unicode {
@@ -893,11 +976,11 @@ mod unicode_spans {
 
     lazy_static::lazy_static! {
         static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, String>> = {
-            let moon_phases = format!("{}", r#"🐄🌑🐄🌒🐄🌓🐄🌔🐄🌕🐄🌖🐄🌗🐄🌘🐄"#);
+            let moon_phases = r#"🐄🌑🐄🌒🐄🌓🐄🌔🐄🌕🐄🌖🐄🌗🐄🌘🐄"#.to_string();
             let invalid_start = 1;
             let invalid_end = "🐄".len() - 1;
-            assert_eq!(moon_phases.is_char_boundary(invalid_start), false);
-            assert_eq!(moon_phases.is_char_boundary(invalid_end), false);
+            assert!(!moon_phases.is_char_boundary(invalid_start));
+            assert!(!moon_phases.is_char_boundary(invalid_end));
             assert_eq!("🐄".len(), 4);
             let file = SimpleFile::new(
                 "moon_jump.rs",
@@ -1053,6 +1136,45 @@ mod multiline_omit {
     test_emit!(rich_no_color);
 }
 
+mod multiline_collapsed_context {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            before_label_lines: 1,
+            after_label_lines: 2,
+            ..Config::default()
+        };
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, String>> = {
+            let mut source = String::from("fn main() {\n");
+            for i in 1..15 {
+                source.push_str(&format!("    let x{} = {};\n", i, i));
+            }
+            source.push('}');
+
+            let file = SimpleFile::new("test", source);
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_message("unused variables")
+                    .with_labels(vec![
+                        Label::primary((), 10..247).with_message("in this block"),
+                        Label::secondary((), 116..118).with_message("`x7` is never read"),
+                    ]),
+                Diagnostic::error()
+                    .with_message("unused variables")
+                    .with_labels(vec![
+                        Label::primary((), 10..247).with_message("in this block"),
+                    ]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+}
+
 mod highlight {
     use super::*;
 
@@ -1154,3 +1276,1828 @@ mod surrounding_lines {
 
     test_emit!(rich_no_color);
 }
+
+mod by_severity {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFiles<&'static str, String>> = {
+            let mut files = SimpleFiles::new();
+
+            let file_id = files.add(
+                "by_severity.rs",
+                unindent::unindent(r#"
+                    fn main() {
+                        let unused = 1;
+                        let x: u32 = "a";
+                    }
+                "#),
+            );
+
+            let diagnostics = vec![
+                Diagnostic::warning()
+                    .with_message("unused variable: `unused`")
+                    .with_labels(vec![Label::primary(file_id, 20..26)]),
+                Diagnostic::note()
+                    .with_message("compiling `by_severity.rs`"),
+                Diagnostic::error()
+                    .with_code("E0308")
+                    .with_message("mismatched types")
+                    .with_labels(vec![Label::primary(file_id, 49..52).with_message("expected `u32`, found `&str`")]),
+                Diagnostic::warning()
+                    .with_message("function is never used: `main`")
+                    .with_labels(vec![Label::primary(file_id, 3..7)]),
+                Diagnostic::bug()
+                    .with_message("internal compiler error"),
+            ];
+
+            TestData { files, diagnostics }
+        };
+    }
+
+    #[test]
+    fn short_color() {
+        let config = Config {
+            display_style: DisplayStyle::Short,
+            ..TEST_CONFIG.clone()
+        };
+
+        insta::assert_snapshot!(TEST_DATA.emit_batch_color(|writer, files, diagnostics| {
+            term::emit_by_severity(writer, &config, files, diagnostics).unwrap()
+        }));
+    }
+
+    #[test]
+    fn rich_no_color() {
+        let config = TEST_CONFIG.clone();
+
+        insta::assert_snapshot!(TEST_DATA.emit_batch_no_color(|writer, files, diagnostics| {
+            term::emit_by_severity(writer, &config, files, diagnostics).unwrap()
+        }));
+    }
+}
+
+mod sub_file {
+    use super::*;
+    use codespan_reporting::files::SubFile;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, String>> = {
+            let file = SimpleFile::new(
+                "users.rs",
+                unindent::unindent(r#"
+                    // Queries used by the user service.
+                    fn find_active_users(db: &DbPool) -> Vec<User> {
+                        db.query("SELECT name
+                            FORM users
+                            WHERE active")
+                    }
+                "#),
+            );
+
+            // The embedded query starts after the opening quote.
+            let query = SubFile::new(&file, (), 100);
+            let diagnostics = vec![
+                query.diagnostic(
+                    Diagnostic::error()
+                        .with_message("expected `FROM`, found `FORM`")
+                        .with_labels(vec![
+                            Label::primary((), 20..24).with_message("unexpected keyword"),
+                            Label::secondary((), 0..6).with_message("in this `SELECT` statement"),
+                        ]),
+                ),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+    test_emit!(short_no_color);
+}
+
+mod dedup_repeated_notes {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            dedup_repeated_notes: true,
+            ..Config::default()
+        };
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("test", "let a = 1 + \"\";\nlet b = 2 + \"\";\nlet c = 3 + \"\";\n");
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_message("mismatched types")
+                    .with_labels(vec![Label::primary((), 12..14).with_message("expected `Int`")])
+                    .with_notes(vec![
+                        "strings can not be added to integers".to_owned(),
+                        "found in binding `a`".to_owned(),
+                    ]),
+                Diagnostic::error()
+                    .with_message("mismatched types")
+                    .with_labels(vec![Label::primary((), 28..30).with_message("expected `Int`")])
+                    .with_notes(vec![
+                        "strings can not be added to integers".to_owned(),
+                        "found in binding `b`".to_owned(),
+                    ]),
+                Diagnostic::error()
+                    .with_message("mismatched types")
+                    .with_labels(vec![Label::primary((), 44..46).with_message("expected `Int`")])
+                    .with_notes(vec![
+                        "strings can not be added to integers".to_owned(),
+                        "found in binding `c`".to_owned(),
+                    ]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    #[test]
+    fn rich_no_color() {
+        let config = TEST_CONFIG.clone();
+
+        insta::assert_snapshot!(TEST_DATA.emit_batch_no_color(|writer, files, diagnostics| {
+            term::emit_all(writer, &config, files, diagnostics).unwrap()
+        }));
+    }
+}
+
+mod top_per_file {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFiles<&'static str, String>> = {
+            let mut files = SimpleFiles::new();
+
+            let main_id = files.add(
+                "main.rs",
+                unindent::unindent(r#"
+                    fn main() {
+                        let unused = 1;
+                        let x: u32 = "a";
+                        let y: u32 = "b";
+                    }
+                "#),
+            );
+            let lib_id = files.add("lib.rs", "pub fn unused() {}\n".to_owned());
+
+            let diagnostics = vec![
+                Diagnostic::warning()
+                    .with_message("unused variable: `unused`")
+                    .with_labels(vec![Label::primary(main_id, 20..26)]),
+                Diagnostic::warning()
+                    .with_message("function is never used: `unused`")
+                    .with_labels(vec![Label::primary(lib_id, 7..13)]),
+                Diagnostic::error()
+                    .with_code("E0308")
+                    .with_message("mismatched types")
+                    .with_labels(vec![Label::primary(main_id, 49..52).with_message("expected `u32`, found `&str`")]),
+                Diagnostic::error()
+                    .with_code("E0308")
+                    .with_message("mismatched types")
+                    .with_labels(vec![Label::primary(main_id, 71..74).with_message("expected `u32`, found `&str`")]),
+                Diagnostic::note()
+                    .with_message("compiling `main.rs`"),
+            ];
+
+            TestData { files, diagnostics }
+        };
+    }
+
+    #[test]
+    fn rich_no_color() {
+        let config = TEST_CONFIG.clone();
+
+        insta::assert_snapshot!(TEST_DATA.emit_batch_no_color(|writer, files, diagnostics| {
+            term::emit_top_per_file(writer, &config, files, diagnostics).unwrap()
+        }));
+    }
+
+    #[test]
+    fn short_no_color() {
+        let config = Config {
+            display_style: DisplayStyle::Short,
+            ..TEST_CONFIG.clone()
+        };
+
+        insta::assert_snapshot!(TEST_DATA.emit_batch_no_color(|writer, files, diagnostics| {
+            term::emit_top_per_file(writer, &config, files, diagnostics).unwrap()
+        }));
+    }
+}
+
+mod side_by_side {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            terminal_width: Some(80),
+            ..Config::default()
+        };
+        static ref TEST_DATA: TestData<'static, SimpleFiles<&'static str, String>> = {
+            let mut files = SimpleFiles::new();
+
+            files.add(
+                "old.rs",
+                unindent::unindent(r#"
+                    fn add(a: i32, b: i32) -> i32 {
+                        a + b
+                    }
+                "#),
+            );
+            files.add(
+                "new.rs",
+                unindent::unindent(r#"
+                    /// Add two numbers, wrapping on overflow.
+                    fn add(a: i64, b: i64) -> i64 {
+                    	a.wrapping_add(b)
+                    }
+                "#),
+            );
+
+            TestData { files, diagnostics: Vec::new() }
+        };
+        static ref LEFT: Label<usize> = Label::secondary(0, 7..21).with_message("before");
+        static ref RIGHT: Label<usize> = Label::primary(1, 43..93).with_message("after");
+    }
+
+    #[test]
+    fn color() {
+        insta::assert_snapshot!(TEST_DATA.emit_batch_color(|writer, files, _| {
+            term::emit_side_by_side(writer, &TEST_CONFIG, files, &LEFT, &RIGHT).unwrap()
+        }));
+    }
+
+    #[test]
+    fn no_color() {
+        insta::assert_snapshot!(TEST_DATA.emit_batch_no_color(|writer, files, _| {
+            term::emit_side_by_side(writer, &TEST_CONFIG, files, &LEFT, &RIGHT).unwrap()
+        }));
+    }
+}
+
+mod compact {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFiles<&'static str, String>> = {
+            let mut files = SimpleFiles::new();
+
+            let file_id = files.add(
+                "compact.rs",
+                unindent::unindent(r#"
+                    fn main() {
+                        let x: u32 = "a";
+                    }
+                "#),
+            );
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_code("E0308")
+                    .with_message("mismatched types")
+                    .with_labels(vec![Label::primary(file_id, 29..32).with_message("expected `u32`, found `&str`")]),
+                Diagnostic::error()
+                    .with_code("E0308")
+                    .with_message("mismatched types")
+                    .with_labels(vec![Label::primary(file_id, 29..32).with_message("expected `u32`, found `&str`")])
+                    .with_notes(vec!["this has a note, so it is rendered richly".to_owned()]),
+                Diagnostic::warning()
+                    .with_message("function is never used: `main`")
+                    .with_labels(vec![Label::primary(file_id, 3..7)]),
+            ];
+
+            TestData { files, diagnostics }
+        };
+    }
+
+    test_emit!(compact_color);
+    test_emit!(compact_no_color);
+}
+
+mod narrow {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            display_style: DisplayStyle::Narrow,
+            terminal_width: Some(40),
+            styles: Styles::with_blue(Color::Blue),
+            ..Config::default()
+        };
+        static ref TEST_DATA: TestData<'static, SimpleFiles<&'static str, String>> = {
+            let mut files = SimpleFiles::new();
+
+            let file_id1 = files.add(
+                "Data/Nat.fun",
+                unindent::unindent(
+                    "
+                        module Data.Nat where
+
+                        data Nat : Type where
+                            zero : Nat
+                            succ : Nat → Nat
+                    ",
+                ),
+            );
+
+            let file_id2 = files.add(
+                "Test.fun",
+                unindent::unindent(
+                    r#"
+                        module Test where
+
+                        _ : Nat
+                        _ = 123 + "hello"
+                    "#,
+                ),
+            );
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_message("mismatched types")
+                    .with_labels(vec![
+                        Label::primary(file_id2, 37..44).with_message("a string literal was found here, but a natural number was expected"),
+                        Label::secondary(file_id1, 28..31).with_message("the type was declared here"),
+                    ])
+                    .with_notes(vec![unindent::unindent(
+                        "
+                            expected type `Nat`
+                               found type `String`, which can not be added to a natural number
+                        ",
+                    )]),
+            ];
+
+            TestData { files, diagnostics }
+        };
+    }
+
+    #[test]
+    fn narrow_color() {
+        insta::assert_snapshot!(TEST_DATA.emit_color(&TEST_CONFIG));
+    }
+
+    #[test]
+    fn narrow_no_color() {
+        insta::assert_snapshot!(TEST_DATA.emit_no_color(&TEST_CONFIG));
+    }
+}
+
+mod code_frame_padding {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            styles: Styles::with_blue(Color::Blue),
+            code_frame_padding: 1,
+            ..Config::default()
+        };
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, String>> = {
+            let file = SimpleFile::new(
+                "padding.rs",
+                unindent::unindent(r#"
+                    fn main() {
+                        let x: u32 = "a";
+                    }
+                "#),
+            );
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_code("E0308")
+                    .with_message("mismatched types")
+                    .with_labels(vec![
+                        Label::primary((), 29..32).with_message("expected `u32`, found `&str`"),
+                        Label::secondary((), 23..26).with_message("expected due to this"),
+                    ]),
+                Diagnostic::error()
+                    .with_message("`main` function has the wrong shape")
+                    .with_labels(vec![Label::primary((), 0..35)])
+                    .with_notes(vec!["a note after the padding".to_owned()]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+}
+
+mod multiline_note {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("note.rs", "let x = 1 +;\n");
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_message("expected expression, found `;`")
+                    .with_labels(vec![Label::primary((), 11..12)])
+                    .with_note(
+                        "expressions are formed from terms and operators:\n    term\n    term + term",
+                    ),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+    test_emit!(short_no_color);
+}
+
+mod dedent_common_indent {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            dedent_common_indent: true,
+            ..Config::default()
+        };
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, String>> = {
+            let file = SimpleFile::new(
+                "nested.rs",
+                unindent::unindent(r#"
+                    mod outer {
+                        mod inner {
+                            fn main() {
+                                if true {
+                                    let x: u32 = "a";
+
+                                    match x {
+                                        0 => "zero",
+                                        _ => 1,
+                                    };
+                                }
+                            }
+                        }
+                    }
+                "#),
+            );
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_code("E0308")
+                    .with_message("mismatched types")
+                    .with_labels(vec![
+                        Label::primary((), 99..102).with_message("expected `u32`, found `&str`"),
+                        Label::secondary((), 93..96).with_message("expected due to this"),
+                    ]),
+                Diagnostic::error()
+                    .with_code("E0308")
+                    .with_message("`match` arms have incompatible types")
+                    .with_labels(vec![
+                        Label::primary((), 189..190).with_message("expected `&str`, found integer"),
+                        Label::secondary((), 156..162).with_message("this is found to be of type `&str`"),
+                        Label::secondary((), 121..210).with_message("`match` arms have incompatible types"),
+                    ]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+}
+
+mod severity_help_urls {
+    use super::*;
+    use codespan_reporting::diagnostic::Severity;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            severity_help_urls: vec![(Severity::Error, "https://example.com/errors".to_owned())]
+                .into_iter()
+                .collect(),
+            ..Config::default()
+        };
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("test", "let x = 1 + \"\";\nlet unused = 2;\n");
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_message("mismatched types")
+                    .with_labels(vec![Label::primary((), 12..14).with_message("expected `Int`")]),
+                Diagnostic::warning()
+                    .with_message("unused variable: `unused`")
+                    .with_labels(vec![Label::primary((), 20..26)]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+    test_emit!(short_no_color);
+}
+
+mod annotated_file {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, String>> = {
+            let file = SimpleFile::new(
+                "main.rs",
+                unindent::unindent(r#"
+                    fn main() {
+                        let x = 1 + "";
+                        let unused = 2;
+                        println!("{}", x);
+                    }
+                "#),
+            );
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_message("mismatched types")
+                    .with_labels(vec![
+                        Label::primary((), 28..30).with_message("expected `Int`, found `String`"),
+                        Label::secondary((), 24..25),
+                    ]),
+                Diagnostic::warning()
+                    .with_message("unused variable: `unused`")
+                    .with_labels(vec![Label::primary((), 40..46)]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    #[test]
+    fn rich_color() {
+        let config = Config::default();
+
+        insta::assert_snapshot!(TEST_DATA.emit_batch_color(|writer, files, diagnostics| {
+            term::emit_annotated_file(writer, &config, files, (), diagnostics).unwrap()
+        }));
+    }
+
+    #[test]
+    fn rich_no_color() {
+        let config = Config::default();
+
+        insta::assert_snapshot!(TEST_DATA.emit_batch_no_color(|writer, files, diagnostics| {
+            term::emit_annotated_file(writer, &config, files, (), diagnostics).unwrap()
+        }));
+    }
+}
+
+mod annotated_file_same_line {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("main.rs", "let unused = 1 + \"\";\n");
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_message("mismatched types")
+                    .with_labels(vec![
+                        Label::primary((), 17..19).with_message("expected `Int`, found `String`"),
+                    ]),
+                Diagnostic::warning()
+                    .with_message("unused variable: `unused`")
+                    .with_labels(vec![Label::primary((), 4..10)]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    #[test]
+    fn rich_color() {
+        let config = Config {
+            styles: Styles::with_blue(Color::Blue),
+            ..Config::default()
+        };
+
+        insta::assert_snapshot!(TEST_DATA.emit_batch_color(|writer, files, diagnostics| {
+            term::emit_annotated_file(writer, &config, files, (), diagnostics).unwrap()
+        }));
+    }
+}
+
+mod message_bullets {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            chars: Chars {
+                primary_message_bullet: "» ".to_owned(),
+                secondary_message_bullet: "· ".to_owned(),
+                ..Chars::default()
+            },
+            ..Config::default()
+        };
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, String>> = {
+            let file = SimpleFile::new(
+                "main.rs",
+                unindent::unindent(r#"
+                    fn main() {
+                        let mut v = vec![1];
+                        let first = &v[0];
+                        v.push(2);
+                        println!("{}", first);
+                    }
+                "#),
+            );
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_code("E0502")
+                    .with_message("cannot borrow `v` as mutable because it is also borrowed as immutable")
+                    .with_labels(vec![
+                        Label::secondary((), 53..58).with_message("immutable borrow occurs here"),
+                        Label::primary((), 64..73).with_message("mutable borrow occurs here"),
+                        Label::secondary((), 64..65).with_message("`v` is borrowed mutably"),
+                        Label::secondary((), 94..99).with_message("immutable borrow later used here"),
+                    ]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_color);
+    test_emit!(rich_no_color);
+}
+
+mod label_detail {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, String>> = {
+            let file = SimpleFile::new(
+                "detail.rs",
+                unindent::unindent(r#"
+                    fn main() {
+                        let s = String::from("hi");
+                        let t = s;
+                        if true {
+                            println!("{}", s);
+                        }
+                    }
+                "#),
+            );
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_code("E0382")
+                    .with_message("borrow of moved value: `s`")
+                    .with_labels(vec![
+                        Label::secondary((), 56..57)
+                            .with_message("value moved here")
+                            .with_detail("`s` has type `String`,\nwhich does not implement the `Copy` trait"),
+                        Label::primary((), 96..97).with_message("value borrowed here after move"),
+                        Label::secondary((), 63..105)
+                            .with_message("in this block")
+                            .with_detail("the block is evaluated\nafter the move"),
+                    ]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+    test_emit!(rich_ascii_no_color);
+}
+
+mod static_files {
+    use super::*;
+    use codespan_reporting::files::StaticFiles;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, StaticFiles> = {
+            let mut files = StaticFiles::new();
+
+            let prelude = files.add("prelude.fun", "fn id(x) = x\nfn const(x, y) = x\n");
+            let main = files.add("main.fun", "fn main() = id(1, 2)\n");
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_message("this function takes 1 argument but 2 arguments were supplied")
+                    .with_labels(vec![
+                        Label::primary(main, 12..20).with_message("expected 1 argument"),
+                        Label::secondary(prelude, 3..8).with_message("defined here"),
+                    ]),
+            ];
+
+            TestData { files, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+    test_emit!(short_no_color);
+}
+
+mod empty_notes {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("notes.rs", "let x = 1 +;\n");
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_message("expected expression, found `;`")
+                    .with_labels(vec![Label::primary((), 11..12)])
+                    .with_notes(vec![
+                        "expected one of `(`, `-`, or a literal".to_owned(),
+                        String::new(),
+                        " \n ".to_owned(),
+                        "operators must be followed by an operand".to_owned(),
+                    ]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+    test_emit!(medium_no_color);
+
+    #[test]
+    fn rich_no_color_keep_empty() {
+        let config = Config {
+            skip_empty_notes: false,
+            ..TEST_CONFIG.clone()
+        };
+
+        insta::assert_snapshot!(TEST_DATA.emit_no_color(&config));
+    }
+}
+
+mod display_column {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, String>> = {
+            let file = SimpleFile::new(
+                "align.txt",
+                unindent::unindent("
+                    items:
+                        - first
+                      - second
+                    \t- third
+                    - fourth
+                "),
+            );
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_message("inconsistent indentation")
+                    .with_labels(vec![
+                        Label::primary((), 19..19)
+                            .with_display_column(5)
+                            .with_message("expected `-` here"),
+                        Label::secondary((), 7..7)
+                            .with_display_column(5)
+                            .with_message("to align with this item"),
+                    ]),
+                Diagnostic::error()
+                    .with_message("inconsistent indentation")
+                    .with_labels(vec![
+                        Label::primary((), 30..30)
+                            .with_display_column(5)
+                            .with_message("expected `-` here"),
+                    ]),
+                Diagnostic::warning()
+                    .with_message("item is not indented")
+                    .with_labels(vec![
+                        Label::primary((), 39..39)
+                            .with_display_column(12)
+                            .with_message("expected the item to continue here"),
+                    ]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+    test_emit!(short_no_color);
+}
+
+mod inline_cross_file_refs {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            inline_cross_file_refs: true,
+            ..Config::default()
+        };
+        static ref TEST_DATA: TestData<'static, SimpleFiles<&'static str, String>> = {
+            let mut files = SimpleFiles::new();
+
+            let main_id = files.add(
+                "main.rs",
+                unindent::unindent(r#"
+                    fn main() {
+                        foo(1, 2);
+                    }
+                "#),
+            );
+            let lib_id = files.add("lib.rs", "pub fn foo(x: u32) {}\n".to_owned());
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_code("E0061")
+                    .with_message("this function takes 1 argument but 2 arguments were supplied")
+                    .with_labels(vec![
+                        Label::primary(main_id, 16..19).with_message("expected 1 argument"),
+                        Label::secondary(main_id, 19..25).with_message("2 arguments supplied"),
+                        Label::secondary(lib_id, 7..10).with_message("defined here"),
+                        Label::secondary(lib_id, 11..17),
+                    ])
+                    .with_notes(vec!["remove the extra argument".to_owned()]),
+                Diagnostic::warning()
+                    .with_message("function is never used: `foo`")
+                    .with_labels(vec![Label::secondary(lib_id, 7..10)]),
+            ];
+
+            TestData { files, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+}
+
+mod certainty {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, String>> = {
+            let file = SimpleFile::new(
+                "main.rs",
+                unindent::unindent(r#"
+                    fn main() {
+                        let x = read_number();
+                        let y = 10 / x;
+                        let z = y - y;
+                        println!("{}", 1 / z);
+                    }
+                "#),
+            );
+
+            let diagnostics = vec![
+                Diagnostic::warning()
+                    .with_message("this operation may panic")
+                    .with_labels(vec![
+                        Label::primary((), 51..57)
+                            .with_message("division by zero")
+                            .with_certainty(30),
+                        Label::secondary((), 97..102)
+                            .with_message("attempt to divide by zero")
+                            .with_certainty(90),
+                    ]),
+                Diagnostic::warning()
+                    .with_message("this operation may panic")
+                    .with_labels(vec![Label::primary((), 51..57).with_certainty(10)]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+    test_emit!(rich_ascii_no_color);
+    test_emit!(compact_no_color);
+}
+
+mod anonymous_file_name {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            anonymous_file_name: "<stdin>".to_owned(),
+            ..Config::default()
+        };
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("", "let x = 1;\nlet y = x +;\n");
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_message("expected an expression after `+`")
+                    .with_labels(vec![Label::primary((), 21..22).with_message("missing operand")]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+    test_emit!(short_no_color);
+}
+
+mod steps {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFiles<&'static str, String>> = {
+            let mut files = SimpleFiles::new();
+
+            let main_id = files.add(
+                "main.rs",
+                unindent::unindent(r#"
+                    fn main() {
+                        let s = String::new();
+                        let t = s;
+                        consume(s);
+                    }
+                "#),
+            );
+            let lib_id = files.add("lib.rs", "pub fn consume(_: String) {}\n".to_owned());
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_code("E0382")
+                    .with_message("use of moved value: `s`")
+                    .with_labels(vec![
+                        Label::primary(main_id, 66..67).with_message("value used here after move"),
+                    ])
+                    .with_steps(vec![
+                        (
+                            Label::secondary(main_id, 20..21).with_message("has type `String`"),
+                            "value created here".to_owned(),
+                        ),
+                        (
+                            Label::secondary(main_id, 51..52),
+                            "value moved here".to_owned(),
+                        ),
+                        (
+                            Label::secondary(lib_id, 7..14),
+                            "value later consumed by this function".to_owned(),
+                        ),
+                    ])
+                    .with_notes(vec!["move occurs because `String` does not implement `Copy`".to_owned()]),
+            ];
+
+            TestData { files, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+}
+
+mod max_line_scan {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            max_line_scan: Some(24),
+            ..Config::default()
+        };
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, String>> = {
+            let mut source = String::from("{\"key\": [1, 2, 3, true, ");
+            while source.len() < 8 * 1024 * 1024 {
+                source.push_str("null, ");
+            }
+            source.push_str("null]}\n");
+            let line_end = source.len() - 1;
+            source.push_str("// end\n");
+
+            let file = SimpleFile::new("huge.json", source);
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_message("expected a number")
+                    .with_labels(vec![
+                        Label::primary((), 19..23).with_message("found a boolean"),
+                        Label::secondary((), 8..9).with_message("in this array"),
+                    ]),
+                Diagnostic::warning()
+                    .with_message("trailing null")
+                    .with_labels(vec![Label::primary((), 8 * 1024 * 1024..8 * 1024 * 1024 + 4)]),
+                Diagnostic::warning()
+                    .with_message("unterminated comment")
+                    .with_labels(vec![
+                        Label::primary((), 150..line_end + 4).with_message("spans two lines"),
+                    ]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+    test_emit!(rich_ascii_no_color);
+
+    #[test]
+    fn narrow_no_color() {
+        let config = Config {
+            display_style: DisplayStyle::Narrow,
+            ..TEST_CONFIG.clone()
+        };
+
+        insta::assert_snapshot!(TEST_DATA.emit_no_color(&config));
+    }
+
+    #[test]
+    fn elm_no_color() {
+        let config = Config {
+            display_style: DisplayStyle::Elm,
+            ..TEST_CONFIG.clone()
+        };
+
+        insta::assert_snapshot!(TEST_DATA.emit_no_color(&config));
+    }
+}
+
+mod same_position_order {
+    use super::*;
+
+    fn emit_labels(labels: Vec<Label<()>>) -> String {
+        let data = TestData {
+            files: SimpleFile::new("same_position", "let x = y;"),
+            diagnostics: vec![Diagnostic::error()
+                .with_message("ambiguous name")
+                .with_labels(labels)],
+        };
+        data.emit_no_color(&TEST_CONFIG)
+    }
+
+    #[test]
+    fn insertion_order_independent() {
+        let labels = vec![
+            Label::secondary((), 8..9).with_message("could refer to the local `y`"),
+            Label::secondary((), 8..9).with_message("could refer to the constant `y`"),
+            Label::secondary((), 8..9).with_message("could refer to the function `y`"),
+            Label::primary((), 4..5),
+        ];
+        let expected = emit_labels(labels.clone());
+
+        let mut reversed = labels.clone();
+        reversed.reverse();
+        assert_eq!(emit_labels(reversed), expected);
+
+        let mut rotated = labels;
+        rotated.rotate_left(1);
+        assert_eq!(emit_labels(rotated), expected);
+
+        insta::assert_snapshot!(expected);
+    }
+}
+
+mod newline_labels {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("newlines", "let x =\nlet y = 1;\r\nlet z = 2;\n");
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_message("expected expression, found newline")
+                    .with_labels(vec![
+                        Label::primary((), 7..8).with_message("expected expression"),
+                        Label::secondary((), 6..7).with_message("after this"),
+                    ]),
+                Diagnostic::warning()
+                    .with_message("statement ends with a CRLF line terminator")
+                    .with_labels(vec![Label::primary((), 17..20).with_message("here")]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_color);
+    test_emit!(rich_no_color);
+    test_emit!(rich_ascii_no_color);
+}
+
+mod gutter_padding {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            gutter_padding_left: 0,
+            gutter_padding_right: 0,
+            ..Config::default()
+        };
+
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new(
+                "gutter_padding",
+                "fn main() {\n    let x = 1 + \"one\";\n}\n",
+            );
+
+            let diagnostics = vec![Diagnostic::error()
+                .with_message("mismatched types")
+                .with_labels(vec![
+                    Label::primary((), 28..33).with_message("expected integer"),
+                    Label::secondary((), 24..25).with_message("because of this"),
+                    Label::secondary((), 10..36).with_message("in this block"),
+                ])
+                .with_notes(vec!["expected `{integer}`, found `&str`".to_owned()])];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+    test_emit!(rich_ascii_no_color);
+}
+
+mod coalesce_identical_labels {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            coalesce_identical_labels: true,
+            ..Config::default()
+        };
+
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("coalesce", "(+ test \"\")\n");
+
+            let label = Label::primary((), 8..10).with_message("expected `Int` but found `String`");
+            let diagnostics = vec![Diagnostic::error()
+                .with_message("mismatched types")
+                .with_labels(vec![
+                    label.clone(),
+                    Label::secondary((), 3..7).with_message("in this call"),
+                    label.clone(),
+                    label,
+                ])];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+    test_emit!(rich_ascii_no_color);
+}
+
+mod severity_hint {
+    use super::*;
+    use codespan_reporting::diagnostic::Severity;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("severity_hint", "let total = count / len;\n");
+
+            let diagnostics = vec![Diagnostic::error()
+                .with_message("attempt to divide by zero")
+                .with_labels(vec![
+                    Label::primary((), 18..19).with_message("division by zero"),
+                    Label::secondary((), 20..23)
+                        .with_message("`len` may be zero here")
+                        .with_detail("`len` is the length of `items`")
+                        .with_severity_hint(Severity::Warning),
+                ])];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_color);
+    test_emit!(rich_no_color);
+
+    #[test]
+    fn narrow_color() {
+        let config = Config {
+            display_style: DisplayStyle::Narrow,
+            styles: Styles::with_blue(Color::Blue),
+            ..Config::default()
+        };
+
+        insta::assert_snapshot!(TEST_DATA.emit_color(&config));
+    }
+
+    #[test]
+    fn elm_color() {
+        let config = Config {
+            display_style: DisplayStyle::Elm,
+            styles: Styles::with_blue(Color::Blue),
+            ..Config::default()
+        };
+
+        insta::assert_snapshot!(TEST_DATA.emit_color(&config));
+    }
+}
+
+mod reveal_whitespace_margin {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            reveal_whitespace_margin: Some(2),
+            ..Config::default()
+        };
+
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("whitespace", "let x = 1;   \n\tlet  y = 2;\n");
+
+            let diagnostics = vec![
+                Diagnostic::warning()
+                    .with_message("trailing whitespace")
+                    .with_labels(vec![Label::primary((), 10..13).with_message("remove this")]),
+                Diagnostic::warning()
+                    .with_message("repeated space")
+                    .with_labels(vec![Label::primary((), 18..20)]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+    test_emit!(rich_ascii_no_color);
+}
+
+mod severity_icons {
+    use super::*;
+    use codespan_reporting::diagnostic::Severity;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = {
+            let mut icons = std::collections::BTreeMap::new();
+            icons.insert(Severity::Error, "❌".to_owned());
+            icons.insert(Severity::Warning, "⚠️".to_owned());
+
+            Config {
+                severity_icons: Some(icons),
+                ..Config::default()
+            }
+        };
+
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("icons", "let x: u32 = \"one\";\n");
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_code("E0308")
+                    .with_message("mismatched types")
+                    .with_labels(vec![Label::primary((), 13..18).with_message("expected `u32`")]),
+                Diagnostic::warning()
+                    .with_message("unused variable: `x`")
+                    .with_labels(vec![Label::primary((), 4..5)]),
+                Diagnostic::note().with_message("checking icons"),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+    test_emit!(short_no_color);
+}
+
+mod synthetic_line {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("synthetic_line.rs", "let x = square!(y);\n");
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_message("cannot find value `y` in this scope")
+                    .with_labels(vec![
+                        Label::secondary((), 8..18).with_message("in this macro invocation"),
+                        Label::primary((), 0..1)
+                            .with_synthetic_line("y * y")
+                            .with_message("not found in this scope"),
+                        Label::secondary((), 4..5).with_synthetic_line("y * y"),
+                    ]),
+                Diagnostic::warning()
+                    .with_message("unused expansion")
+                    .with_labels(vec![Label::primary((), 0..5)
+                        .with_synthetic_line("x + 1")
+                        .with_message("this value is never used")]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_color);
+    test_emit!(rich_no_color);
+    test_emit!(rich_ascii_no_color);
+    test_emit!(short_no_color);
+}
+
+mod minimal_chars {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, String>> = {
+            let source = unindent::unindent(
+                r#"
+                    fn main() {
+                        let x = match y {
+                            Some(z) => z,
+                            None => 0,
+                        };
+                    }
+                "#,
+            );
+            let file = SimpleFile::new("minimal.rs", source);
+
+            let diagnostics = vec![Diagnostic::error()
+                .with_message("cannot find value `y` in this scope")
+                .with_labels(vec![
+                    Label::primary((), 30..31).with_message("not found in this scope"),
+                    Label::secondary((), 24..80).with_message("in this `match`"),
+                ])
+                .with_notes(vec![
+                    "help: a local variable with a similar name exists: `x`".to_owned(),
+                ])];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+    test_emit!(rich_minimal_no_color);
+}
+
+mod empty_message_text {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            empty_message_text: Some("<no message>".to_owned()),
+            ..Config::default()
+        };
+
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("empty_message", "let x = 1;\n");
+
+            let diagnostics = vec![
+                Diagnostic::error().with_labels(vec![Label::primary((), 4..5)]),
+                Diagnostic::warning()
+                    .with_labels(vec![Label::primary((), 4..5)])
+                    .with_notes(vec![
+                        "unused variable `x`".to_owned(),
+                        "help: prefix it with an underscore: `_x`".to_owned(),
+                    ]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+    test_emit!(short_no_color);
+}
+
+mod message_only {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("message_only", "let total = count / len;\n");
+
+            let diagnostics = vec![
+                Diagnostic::note()
+                    .with_message("this line was changed")
+                    .with_labels(vec![Label::primary((), 0..23)
+                        .with_message("introduced in the last commit")
+                        .with_message_only(true)]),
+                Diagnostic::error()
+                    .with_message("attempt to divide by zero")
+                    .with_labels(vec![
+                        Label::primary((), 18..19).with_message("division by zero"),
+                        Label::secondary((), 0..23)
+                            .with_message("this line was changed in the last commit")
+                            .with_message_only(true),
+                    ]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_color);
+    test_emit!(rich_no_color);
+}
+
+mod group_line_number_digits {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            group_line_number_digits: true,
+            ..Config::default()
+        };
+
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, String>> = {
+            let mut source = "\n".repeat(998);
+            source.push_str("let x = 1;\n");
+            source.push_str(&"\n".repeat(123_456 - 1000));
+            source.push_str("let y = x;\n");
+            let x_start = source.find("let x").unwrap();
+            let y_start = source.find("let y").unwrap();
+            let file = SimpleFile::new("huge_file", source);
+
+            let diagnostics = vec![Diagnostic::warning()
+                .with_message("unused variable `y`")
+                .with_labels(vec![
+                    Label::primary((), (y_start + 4)..(y_start + 5)),
+                    Label::secondary((), (x_start + 4)..(x_start + 5))
+                        .with_message("`x` is defined here"),
+                ])];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+}
+
+mod fold_secondary_labels {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            fold_secondary_labels: true,
+            ..Config::default()
+        };
+
+        static ref TEST_DATA: TestData<'static, SimpleFiles<&'static str, &'static str>> = {
+            let mut files = SimpleFiles::new();
+            let main = files.add("main.rs", "use lib::Config;\n\nfn main() {\n    let config = Config::new(1);\n}\n");
+            let lib = files.add("lib.rs", "pub struct Config;\n\nimpl Config {\n    pub fn new() -> Config { Config }\n}\n");
+
+            let diagnostics = vec![Diagnostic::error()
+                .with_message("this function takes 0 arguments but 1 argument was supplied")
+                .with_labels(vec![
+                    Label::primary(main, 47..58).with_message("expected 0 arguments"),
+                    Label::secondary(main, 59..60).with_message("supplied 1 argument"),
+                    Label::secondary(main, 4..15).with_message("imported here"),
+                    Label::secondary(lib, 45..48).with_message("defined here"),
+                ])
+                .with_notes(vec!["help: remove the extra argument".to_owned()])];
+
+            TestData { files, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+    test_emit!(medium_no_color);
+}
+
+mod header_and_label_styles {
+    use super::*;
+    use codespan_reporting::term::termcolor::ColorSpec;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            styles: Styles {
+                header_error: ColorSpec::new()
+                    .set_fg(Some(Color::Red))
+                    .set_bold(true)
+                    .clone(),
+                primary_label_error: ColorSpec::new()
+                    .set_fg(Some(Color::Magenta))
+                    .clone(),
+                ..Styles::with_blue(Color::Blue)
+            },
+            ..Config::default()
+        };
+
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("styles", "let x: u32 = \"one\";\n");
+
+            let diagnostics = vec![Diagnostic::error()
+                .with_message("mismatched types")
+                .with_labels(vec![
+                    Label::primary((), 13..18).with_message("expected `u32`, found `&str`"),
+                    Label::secondary((), 7..10).with_message("expected due to this"),
+                ])];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_color);
+}
+
+mod per_file_width {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            display_style: DisplayStyle::Narrow,
+            terminal_width: Some(80),
+            per_file_width: true,
+            ..Config::default()
+        };
+
+        static ref TEST_DATA: TestData<'static, SimpleFiles<&'static str, &'static str>> = {
+            let mut files = SimpleFiles::new();
+            let short = files.add("short.rs", "let n = 1;\n");
+            let long = files.add(
+                "long.rs",
+                "let total = first_value + second_value + third_value + fourth_value;\n",
+            );
+
+            let message = "this value is used in an arithmetic expression that may overflow at runtime";
+            let diagnostics = vec![Diagnostic::warning()
+                .with_message("possible arithmetic overflow")
+                .with_labels(vec![
+                    Label::primary(long, 12..67).with_message(message),
+                    Label::secondary(short, 8..9).with_message(message),
+                ])];
+
+            TestData { files, diagnostics }
+        };
+    }
+
+    #[test]
+    fn narrow_no_color() {
+        insta::assert_snapshot!(TEST_DATA.emit_no_color(&TEST_CONFIG));
+    }
+}
+
+mod elm {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            display_style: DisplayStyle::Elm,
+            terminal_width: Some(40),
+            ..Config::default()
+        };
+
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("Main.elm", "main =\n    add \"hello\" 1\n");
+
+            let diagnostics = vec![Diagnostic::error()
+                .with_message("type mismatch")
+                .with_labels(vec![Label::primary((), 15..22).with_message(
+                    "The 1st argument to `add` is not what I expect, it is a string of type `String`",
+                )])
+                .with_notes(vec![String::from(
+                    "expected type `Int`\n     found type `String`",
+                )])];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    #[test]
+    fn elm_no_color() {
+        insta::assert_snapshot!(TEST_DATA.emit_no_color(&TEST_CONFIG));
+    }
+}
+
+mod code_badge_line {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            code_badge_line: true,
+            ..Config::default()
+        };
+
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("main.rs", "let x: Foo = 1;\n");
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_code("E0412")
+                    .with_message("cannot find type `Foo` in this scope")
+                    .with_labels(vec![Label::primary((), 7..10).with_message("not found in this scope")]),
+                Diagnostic::warning().with_message("unused variable: `x`"),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+    test_emit!(short_no_color);
+}
+
+mod annotate_caret_column {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            annotate_caret_column: true,
+            ..Config::default()
+        };
+
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("test", "(+ test \"\")\n");
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_message("expected a number")
+                    .with_labels(vec![
+                        Label::primary((), 3..4).with_message("expected a number"),
+                        Label::secondary((), 1..2).with_message("in this application"),
+                    ]),
+                Diagnostic::error()
+                    .with_message("unexpected type in `+` application")
+                    .with_labels(vec![
+                        Label::primary((), 8..10).with_message("expected `Int` but found `String`"),
+                    ]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+}
+
+mod merge_adjacent_labels {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            merge_adjacent_labels: true,
+            ..Config::default()
+        };
+
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("test", "let x = @#$ + 1;\n");
+
+            let diagnostics = vec![Diagnostic::error()
+                .with_message("unexpected characters")
+                .with_labels(vec![
+                    Label::primary((), 8..9),
+                    Label::primary((), 10..11),
+                    Label::primary((), 9..10),
+                    Label::secondary((), 4..5).with_message("in this binding"),
+                ])];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+}
+
+mod same_range_messages {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("same_range", "let total = count + 1;\n");
+
+            let diagnostics = vec![Diagnostic::warning()
+                .with_message("suspicious arithmetic")
+                .with_labels(vec![
+                    Label::primary((), 12..17).with_message("this may overflow"),
+                    Label::primary((), 12..17).with_message("this is never negative"),
+                ])];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+}
+
+mod focus_label {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            focus_label: Some(1),
+            styles: Styles::with_blue(Color::Blue),
+            ..Config::default()
+        };
+
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new(
+                "tour.rs",
+                "let x = 1;\nlet y = x + 1;\nlet z = y * 2;\n",
+            );
+
+            let diagnostics = vec![Diagnostic::note()
+                .with_message("how values flow")
+                .with_labels(vec![
+                    Label::secondary((), 4..5).with_message("`x` is defined here"),
+                    Label::primary((), 19..24).with_message("`x` is used here"),
+                    Label::secondary((), 30..31).with_message("`y` is used here"),
+                ])];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_color);
+    test_emit!(rich_no_color);
+}
+
+mod message_above_underline {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            message_above_underline: true,
+            ..Config::default()
+        };
+
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, &'static str>> = {
+            let file = SimpleFile::new("test", "(+ test \"\")\nlet x: u32 = \"one\";\n");
+
+            let diagnostics = vec![
+                Diagnostic::error()
+                    .with_message("unexpected type in `+` application")
+                    .with_labels(vec![
+                        Label::primary((), 8..10).with_message("expected `Int` but found `String`"),
+                    ]),
+                Diagnostic::error()
+                    .with_message("mismatched types")
+                    .with_labels(vec![
+                        Label::primary((), 25..30).with_message("expected `u32`, found `&str`"),
+                        Label::secondary((), 19..22).with_message("expected due to this"),
+                    ]),
+            ];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+}
+
+mod gutter_align_left {
+    use super::*;
+
+    use codespan_reporting::term::GutterAlign;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            gutter_align: GutterAlign::Left,
+            ..Config::default()
+        };
+
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, String>> = {
+            let mut source = "\n".repeat(8);
+            source.push_str("let x = 1;\n");
+            source.push_str("let y = x +\n");
+            source.push_str("    z;\n");
+            let x_start = source.find("let x").unwrap();
+            let y_start = source.find("let y").unwrap();
+            let z_start = source.find('z').unwrap();
+            let file = SimpleFile::new("main.fun", source);
+
+            let diagnostics = vec![Diagnostic::error()
+                .with_message("cannot find value `z` in this scope")
+                .with_labels(vec![
+                    Label::primary((), z_start..(z_start + 1)).with_message("not found in this scope"),
+                    Label::secondary((), (y_start + 8)..(z_start + 1))
+                        .with_message("in this expression"),
+                    Label::secondary((), (x_start + 4)..(x_start + 5))
+                        .with_message("a similar name exists"),
+                ])];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+}
+
+mod max_snippet_blocks {
+    use super::*;
+
+    lazy_static::lazy_static! {
+        static ref TEST_CONFIG: Config = Config {
+            max_snippet_blocks: Some(2),
+            ..Config::default()
+        };
+
+        static ref TEST_DATA: TestData<'static, SimpleFile<&'static str, String>> = {
+            let mut source = String::new();
+            let mut starts = Vec::new();
+            for region in 0..6 {
+                source.push_str(&"\n".repeat(10));
+                starts.push(source.len() + 4);
+                source.push_str(&format!("let x{} = {};\n", region, region));
+            }
+            let file = SimpleFile::new("regions.fun", source);
+
+            let mut labels = (0..6)
+                .map(|region| {
+                    Label::secondary((), starts[region]..(starts[region] + 2))
+                        .with_message("assigned here")
+                })
+                .collect::<Vec<_>>();
+            labels[3] = Label::primary((), starts[3]..(starts[3] + 2))
+                .with_message("value is never read");
+
+            let diagnostics = vec![Diagnostic::warning()
+                .with_message("variable `x` is assigned to, but never used")
+                .with_labels(labels)
+                .with_notes(vec!["consider using `_x` instead".to_owned()])];
+
+            TestData { files: file, diagnostics }
+        };
+    }
+
+    test_emit!(rich_no_color);
+}
@@ -235,7 +235,7 @@ where
         Ok(PathBuf::from(self.name(id)).display().to_string())
     }
 
-    fn source(&'a self, id: FileId) -> Result<&str, Error> {
+    fn source(&'a self, id: FileId) -> Result<&'a str, Error> {
         Ok(self.source(id).as_ref())
     }
 
@@ -252,6 +252,10 @@ where
 
         Ok(span.start().to_usize()..span.end().to_usize())
     }
+
+    fn file_ids(&'a self) -> Box<dyn 'a + Iterator<Item = FileId>> {
+        Box::new((0..self.files.len()).map(FileId::new))
+    }
 }
 
 /// A file that is stored in the database.